@@ -62,10 +62,14 @@ lazy_static! {
   pub static ref STRUCTURE_EXTENSION_IMG: RgbaImage = include_image!("structures", "extension.png");
   pub static ref STRUCTURE_EXTRACTOR_IMG: RgbaImage = include_image!("structures", "extractor.png");
   pub static ref STRUCTURE_FACTORY_IMG: RgbaImage = include_image!("structures", "factory.png");
+  pub static ref STRUCTURE_INVADERCORE_IMG: RgbaImage = include_image!("structures", "invaderCore.png");
+  pub static ref STRUCTURE_KEEPERLAIR_IMG: RgbaImage = include_image!("structures", "keeperLair.png");
   pub static ref STRUCTURE_LAB_IMG: RgbaImage = include_image!("structures", "lab.png");
   pub static ref STRUCTURE_LINK_IMG: RgbaImage = include_image!("structures", "link.png");
   pub static ref STRUCTURE_NUKER_IMG: RgbaImage = include_image!("structures", "nuker.png");
   pub static ref STRUCTURE_OBSERVER_IMG: RgbaImage = include_image!("structures", "observer.png");
+  pub static ref STRUCTURE_PORTAL_IMG: RgbaImage = include_image!("structures", "portal.png");
+  pub static ref STRUCTURE_POWERBANK_IMG: RgbaImage = include_image!("structures", "powerBank.png");
   pub static ref STRUCTURE_POWERSPAWN_IMG: RgbaImage = include_image!("structures", "powerSpawn.png");
   pub static ref STRUCTURE_RAMPART_IMG: RgbaImage = include_image!("structures", "rampart.png");
   pub static ref STRUCTURE_ROAD_IMG: RgbaImage = include_image!("structures", "road.png");
@@ -74,4 +78,12 @@ lazy_static! {
   pub static ref STRUCTURE_TERMINAL_IMG: RgbaImage = include_image!("structures", "terminal.png");
   pub static ref STRUCTURE_TOWER_IMG: RgbaImage = include_image!("structures", "tower.png");
   pub static ref STRUCTURE_UNKNOWN_IMG: RgbaImage = include_image!("structures", "icon.png");
+
+  pub static ref RUIN_IMG: RgbaImage = include_image!("structures", "ruin.png");
+  pub static ref TOMBSTONE_IMG: RgbaImage = include_image!("structures", "tombstone.png");
+
+  pub static ref DEPOSIT_BIOMASS_IMG: RgbaImage = include_image!("deposits", "biomass.png");
+  pub static ref DEPOSIT_METAL_IMG: RgbaImage = include_image!("deposits", "metal.png");
+  pub static ref DEPOSIT_MIST_IMG: RgbaImage = include_image!("deposits", "mist.png");
+  pub static ref DEPOSIT_SILICON_IMG: RgbaImage = include_image!("deposits", "silicon.png");
 }
@@ -1,5 +1,50 @@
 use crate::assets_data;
 
+mod buildings_plan;
+mod canvas;
+mod colormap;
+mod context;
+mod cost_matrix;
+mod creep;
+mod heatmap;
+mod legend;
+mod object;
+mod offline;
+mod overview;
+mod path;
+mod plan;
+mod range;
+mod replay;
+mod resource;
+mod road;
+mod stack;
+mod terrain;
+mod tower;
+mod visual;
+mod world;
+pub use buildings_plan::BuildingsPlanError;
+pub use canvas::{CanvasError, CanvasLocation, RoomCanvas};
+pub use colormap::{Categorical, Colormap, Gradient, Magma, Plasma, RedBlue, Viridis};
+pub use context::{RenderContext, Theme};
+pub use cost_matrix::CostMatrixOptions;
+pub use creep::{body_part_color, draw_creep_xy, draw_creep_xy_with_scale_factor, DEFAULT_CREEP_OWNER_COLOR};
+pub use heatmap::{draw_heatmap, draw_heatmap_with_scale_factor, HeatmapOptions, HeatmapSource};
+pub use legend::{append_legend_panel, Legend, LegendItem};
+pub use object::{draw_deposit_tile_xy, draw_deposit_tile_xy_with_scale_factor, draw_object_xy, draw_object_xy_with_scale_factor, Deposit, RenderableObject};
+pub use offline::{render_offline_room, render_world_region, render_world_region_with_options, RenderOptions, WorldRenderOptions};
+pub use overview::{render_shard_overview, render_shard_overview_with_options, sector_room_type, OverviewOptions, OverviewPalette, SectorRoomType};
+pub use path::{draw_path, draw_path_with_scale_factor, parse_serialized_path, PathParseError, PathStyle};
+pub use plan::{render_base_plan, render_base_plan_stages, BasePlan, BasePlanOptions, OverbuiltStructure, PlannedStructure};
+pub use range::{draw_range, draw_range_sources, draw_range_sources_with_scale_factor, draw_range_with_scale_factor, tiles_in_range, tower_effectiveness, RangeFalloff, RangeSource};
+pub use replay::{parse_visuals, render_visuals, render_visuals_with_scale_factor, VisualParseError};
+pub use resource::{draw_resource_type_icon_xy, draw_resource_type_icon_xy_with_scale_factor, resource_color};
+pub use road::{draw_roads, draw_roads_with_scale_factor, RoadStyle};
+pub use stack::{draw_structure_stacks, draw_structure_stacks_with_scale_factor, StructureStackStyle};
+pub use terrain::{draw_room_terrain, draw_room_terrain_with_scale_factor, TerrainStyle};
+pub use tower::{draw_tower_effectiveness, draw_tower_effectiveness_with_scale_factor, tower_power_at, tower_sources, TowerAction};
+pub use visual::{parse_color, CircleStyle, FontStyle, LineDrawStyle, LineStyle, PolyStyle, RectStyle, TextAlign, TextStyle, Visual};
+pub use world::WorldCanvas;
+
 pub use screeps::constants::Terrain;
 
 use screeps::constants::{
@@ -24,11 +69,14 @@ pub const DEFAULT_ROOM_MAX_COLUMNS: u32 = ROOM_SIZE as u32;
 /// The default number of rows in a room (y-coordinate)
 pub const DEFAULT_ROOM_MAX_ROWS: u32 = ROOM_SIZE as u32;
 
+/// The number of cells in a room
+const ROOM_AREA: usize = (ROOM_SIZE as usize) * (ROOM_SIZE as usize);
+
 /// The default scaling factor for the final image, meaning the number of pixels allocated for each room cell
 pub const DEFAULT_SCALE_FACTOR: u32 = 50;
 
 /// Represents the various different types of resources that can exist in a room
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Resource {
   Source,
   Hydrogen,
@@ -38,11 +86,13 @@ pub enum Resource {
   Utrium,
   Zynthium,
   Catalyst,
+  /// Any other resource type, drawn as a procedurally generated icon
+  Other(ResourceType),
   Unknown,
 }
 
 /// Represents the various types of player-buildable structures
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BuildableStructure {
   ConstructedWall,
   Container,
@@ -50,10 +100,14 @@ pub enum BuildableStructure {
   Extension,
   Extractor,
   Factory,
+  InvaderCore,
+  KeeperLair,
   Lab,
   Link,
   Nuker,
   Observer,
+  Portal,
+  PowerBank,
   PowerSpawn,
   Rampart,
   Road,
@@ -64,6 +118,32 @@ pub enum BuildableStructure {
   Unknown,
 }
 
+/// Errors that can occur when converting game data into renderable types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionError {
+  /// The object being converted is not a structure
+  NotAStructure,
+  /// The object being converted is not a resource
+  NotAResource,
+  /// The object being converted is not a deposit
+  NotADeposit,
+  /// The object's kind isn't known, so there is nothing to convert it to
+  UnknownObject,
+}
+
+impl std::fmt::Display for ConversionError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ConversionError::NotAStructure => write!(f, "object is not a structure"),
+      ConversionError::NotAResource => write!(f, "object is not a resource"),
+      ConversionError::NotADeposit => write!(f, "object is not a deposit"),
+      ConversionError::UnknownObject => write!(f, "object is of an unknown kind"),
+    }
+  }
+}
+
+impl std::error::Error for ConversionError {}
+
 impl From<Source> for Resource {
     #[inline]
     fn from(_source: Source) -> Resource {
@@ -73,10 +153,8 @@ impl From<Source> for Resource {
 
 // TODO: I'm pretty sure you only need to implement for the reference.
 // once compiling, try taking it out and seeing what happens.
-// 
-// Also, like none of these fail. What are they being used with?
 impl TryFrom<&ResourceType> for Resource {
-    type Error = ();
+    type Error = ConversionError;
 
     #[inline]
     fn try_from(resource_type: &ResourceType) -> Result<Resource, Self::Error> {
@@ -88,13 +166,13 @@ impl TryFrom<&ResourceType> for Resource {
             ResourceType::Utrium     => Resource::Utrium,
             ResourceType::Zynthium   => Resource::Zynthium,
             ResourceType::Catalyst   => Resource::Catalyst,
-            _                        => Resource::Unknown,
+            other                    => Resource::Other(*other),
         })
     }
 }
 
 impl TryFrom<ResourceType> for Resource {
-    type Error = ();
+    type Error = ConversionError;
 
     #[inline]
     fn try_from(resource_type: ResourceType) -> Result<Resource, Self::Error> {
@@ -103,31 +181,20 @@ impl TryFrom<ResourceType> for Resource {
 }
 
 impl TryFrom<&OfflineObject> for Resource {
-    type Error = ();
+    type Error = ConversionError;
 
     #[inline]
     fn try_from(obj: &OfflineObject) -> Result<Resource, Self::Error> {
       match obj {
         OfflineObject::Source { .. } => Ok(Resource::Source),
-        OfflineObject::Mineral { mineral_type, .. } => {
-          Ok(match mineral_type {
-              ResourceType::Hydrogen   => Resource::Hydrogen,
-              ResourceType::Oxygen     => Resource::Oxygen,
-              ResourceType::Keanium    => Resource::Keanium,
-              ResourceType::Lemergium  => Resource::Lemergium,
-              ResourceType::Utrium     => Resource::Utrium,
-              ResourceType::Zynthium   => Resource::Zynthium,
-              ResourceType::Catalyst   => Resource::Catalyst,
-              _                        => Resource::Unknown,
-          })
-        },
-        _ => Ok(Resource::Unknown)
+        OfflineObject::Mineral { mineral_type, .. } => mineral_type.try_into(),
+        _ => Err(ConversionError::NotAResource)
       }
     }
 }
 
 impl TryFrom<OfflineObject> for Resource {
-    type Error = ();
+    type Error = ConversionError;
 
     #[inline]
     fn try_from(obj: OfflineObject) -> Result<Resource, Self::Error> {
@@ -136,7 +203,7 @@ impl TryFrom<OfflineObject> for Resource {
 }
 
 impl TryFrom<StructureType> for BuildableStructure {
-    type Error = ();
+    type Error = ConversionError;
 
     #[inline]
     fn try_from(structure_type: StructureType) -> Result<BuildableStructure, Self::Error> {
@@ -147,10 +214,14 @@ impl TryFrom<StructureType> for BuildableStructure {
             StructureType::Extension   => BuildableStructure::Extension,
             StructureType::Extractor   => BuildableStructure::Extractor,
             StructureType::Factory     => BuildableStructure::Factory,
+            StructureType::InvaderCore => BuildableStructure::InvaderCore,
+            StructureType::KeeperLair  => BuildableStructure::KeeperLair,
             StructureType::Lab         => BuildableStructure::Lab,
             StructureType::Link        => BuildableStructure::Link,
             StructureType::Nuker       => BuildableStructure::Nuker,
             StructureType::Observer    => BuildableStructure::Observer,
+            StructureType::Portal      => BuildableStructure::Portal,
+            StructureType::PowerBank   => BuildableStructure::PowerBank,
             StructureType::PowerSpawn  => BuildableStructure::PowerSpawn,
             StructureType::Rampart     => BuildableStructure::Rampart,
             StructureType::Road        => BuildableStructure::Road,
@@ -164,7 +235,7 @@ impl TryFrom<StructureType> for BuildableStructure {
 }
 
 impl TryFrom<&StructureType> for BuildableStructure {
-    type Error = ();
+    type Error = ConversionError;
 
     #[inline]
     fn try_from(structure_type: &StructureType) -> Result<BuildableStructure, Self::Error> {
@@ -173,22 +244,26 @@ impl TryFrom<&StructureType> for BuildableStructure {
 }
 
 impl TryFrom<&OfflineObject> for BuildableStructure {
-  type Error = ();
+  type Error = ConversionError;
 
   #[inline]
     fn try_from(obj: &OfflineObject) -> Result<BuildableStructure, Self::Error> {
-        Ok(match obj {
-            OfflineObject::ConstructedWall { .. } => BuildableStructure::ConstructedWall,
-            OfflineObject::Controller { .. } => BuildableStructure::Controller,
-            OfflineObject::Extractor { .. } => BuildableStructure::Extractor,
-            OfflineObject::Terminal { .. } => BuildableStructure::Terminal,
-            _                          => BuildableStructure::Unknown,
-        })
+        match obj {
+            OfflineObject::ConstructedWall { .. } => Ok(BuildableStructure::ConstructedWall),
+            OfflineObject::Controller { .. }      => Ok(BuildableStructure::Controller),
+            OfflineObject::Extractor { .. }       => Ok(BuildableStructure::Extractor),
+            OfflineObject::KeeperLair { .. }      => Ok(BuildableStructure::KeeperLair),
+            OfflineObject::Portal { .. }          => Ok(BuildableStructure::Portal),
+            OfflineObject::Terminal { .. }        => Ok(BuildableStructure::Terminal),
+            OfflineObject::Mineral { .. }         => Err(ConversionError::NotAStructure),
+            OfflineObject::Source { .. }          => Err(ConversionError::NotAStructure),
+            OfflineObject::Unknown                => Err(ConversionError::UnknownObject),
+        }
     }
 }
 
 impl TryFrom<OfflineObject> for BuildableStructure {
-  type Error = ();
+  type Error = ConversionError;
 
   #[inline]
     fn try_from(obj: OfflineObject) -> Result<BuildableStructure, Self::Error> {
@@ -198,21 +273,16 @@ impl TryFrom<OfflineObject> for BuildableStructure {
 
 /// Creates an image with default size parameters
 pub fn create_image() -> OutputImage {
-  create_image_with_size_params(DEFAULT_ROOM_MAX_COLUMNS, DEFAULT_ROOM_MAX_ROWS, DEFAULT_SCALE_FACTOR)
+  RenderContext::default().create_image()
 }
 
 /// Creates an image with user-supplied size parameters
 pub fn create_image_with_size_params(room_max_cols: u32, room_max_rows: u32, scale_factor: u32) -> OutputImage {
-  let mut imgbuf = image::ImageBuffer::new((room_max_cols * scale_factor) + 1 as u32, (room_max_rows * scale_factor) + 1 as u32);
-
-  for (_x, _y, pixel) in imgbuf.enumerate_pixels_mut() {
-    let r: u8 = 0;
-    let g: u8 = 0;
-    let b: u8 = 0;
-    *pixel = image::Rgba([r, g, b, 255]);
-  }
-
-  imgbuf
+  RenderContext {
+    room_max_cols,
+    room_max_rows,
+    ..RenderContext::with_scale_factor(scale_factor)
+  }.create_image()
 }
 
 /// Draws a grid on a default-sized image
@@ -222,14 +292,12 @@ pub fn draw_grid(imgbuf: &mut OutputImage)  {
 
 /// Draws a grid on an image with user-supplied scaling
 pub fn draw_grid_with_scale_factor(imgbuf: &mut OutputImage, scale_factor: u32) {
-  for (x, y, pixel) in imgbuf.enumerate_pixels_mut() {
-    if (x % scale_factor == 0) | (y % scale_factor == 0)  {
-      let r: u8 = 255;
-      let g: u8 = 255;
-      let b: u8 = 255;
-      *pixel = image::Rgba([r, g, b, 128]);
-    }
-  }
+  // Cover the whole image, which may not be sized for a default room
+  RenderContext {
+    room_max_cols: imgbuf.width().div_ceil(scale_factor),
+    room_max_rows: imgbuf.height().div_ceil(scale_factor),
+    ..RenderContext::with_scale_factor(scale_factor)
+  }.draw_grid(imgbuf)
 }
 
 /// Calculates a scale that fits the given text within the given
@@ -241,11 +309,22 @@ pub fn draw_grid_with_scale_factor(imgbuf: &mut OutputImage, scale_factor: u32)
 ///
 /// Also returns the width and height of the text with the new scale.
 fn calculate_centered_text_scale(font: &rusttype::Font, area: u32, text: &str) -> (rusttype::Scale, u32, u32) {
-  let default_scale = rusttype::Scale::uniform(area as f32);
+  calculate_text_scale_within(font, area, area, text)
+}
+
+/// Calculates a scale that fits the given text within the given
+/// rectangular area.
+///
+/// The text starts out as tall as the area, and is only shrunk if
+/// it would be wider than the area.
+///
+/// Also returns the width and height of the text with the new scale.
+fn calculate_text_scale_within(font: &rusttype::Font, width: u32, height: u32, text: &str) -> (rusttype::Scale, u32, u32) {
+  let default_scale = rusttype::Scale::uniform(height as f32);
   let (x,y) = imageproc::drawing::text_size(default_scale, font, text);
-  if x > area as i32 {
-    let ratio = (area as f32) / (x as f32);
-    let new_scale_factor = area as f32 * ratio;
+  if x > width as i32 {
+    let ratio = (width as f32) / (x as f32);
+    let new_scale_factor = height as f32 * ratio;
     let new_scale = rusttype::Scale::uniform(new_scale_factor);
     let (x,y) = imageproc::drawing::text_size(new_scale, font, text);
     (new_scale, x as u32, y as u32)
@@ -258,18 +337,29 @@ fn calculate_centered_text_scale(font: &rusttype::Font, area: u32, text: &str) -
 ///
 /// Will scale the text down to fit.
 pub fn draw_centered_text_number_xy(imgbuf: &mut OutputImage, col: u32, row: u32, text: &str) {
+  draw_centered_text_number_xy_with_scale_factor(imgbuf, col, row, text, DEFAULT_SCALE_FACTOR)
+}
+
+/// Draws a centered text number on a user-sized image at a specific cell location.
+///
+/// Will scale the text down to fit.
+///
+/// Keeps the original placement, measured from the cell's corner including the
+/// grid line; [RenderContext::draw_centered_text_number_xy] centers within the
+/// cell's contents instead.
+pub fn draw_centered_text_number_xy_with_scale_factor(imgbuf: &mut OutputImage, col: u32, row: u32, text: &str, scale_factor: u32) {
   let font = &assets_data::FREE_MONO_FONT;
-  let cell_size = DEFAULT_SCALE_FACTOR; // cell_size in pixels.
+  let cell_size = scale_factor; // cell_size in pixels.
   // we want some borders between text, so we need to define an area
   // we'll draw the text within.
   let border_size = 2;
-  let text_area = cell_size - 2*border_size;
+  let text_area = cell_size.saturating_sub(2*border_size).max(1);
   let (scale, width, height) = calculate_centered_text_scale(font, text_area, text);
-  let x_offset = (cell_size - width)/2;
-  let y_offset = (cell_size - height)/2;
-  let x = (col * cell_size + border_size + x_offset).try_into().unwrap();
-  let y = (row * cell_size + border_size + y_offset).try_into().unwrap();
-  imageproc::drawing::draw_text_mut(imgbuf, image::Rgba([255,255,255,255]), x, y, scale, &font, text);
+  let x_offset = cell_size.saturating_sub(width)/2;
+  let y_offset = cell_size.saturating_sub(height)/2;
+  let x = (col * cell_size + border_size + x_offset) as i32;
+  let y = (row * cell_size + border_size + y_offset) as i32;
+  imageproc::drawing::draw_text_mut(imgbuf, image::Rgba([255,255,255,255]), x, y, scale, font, text);
 }
 
 /// Draws a text number on a default-sized image at a specific cell location
@@ -279,112 +369,39 @@ pub fn draw_text_number_xy(imgbuf: &mut OutputImage, col: u32, row: u32, text: &
 
 /// Draws a text number on a user-sized image at a specific cell location
 pub fn draw_text_number_xy_with_scale_factor(imgbuf: &mut OutputImage, col: u32, row: u32, text: &str, scale_factor: u32, text_scale_factor: u32) {
-  let x = (col * scale_factor + 2).try_into().unwrap();
-  let y = (row * scale_factor + 2).try_into().unwrap();
-  draw_text_number_raw(imgbuf, x, y, text, text_scale_factor as f32);
-}
-
-/// Underlying function for drawing numbers on an image at a specific cell location
-fn draw_text_number_raw(imgbuf: &mut OutputImage, x: i32, y: i32, text: &str, text_scale_factor: f32) {
-  // let scale = rusttype::Scale::uniform(15.0);
-  let scale = rusttype::Scale::uniform(text_scale_factor);
-  let font = &assets_data::FREE_MONO_FONT;
-  imageproc::drawing::draw_text_mut(imgbuf, image::Rgba([255,255,255,255]), x, y, scale, &font, text);
+  RenderContext::with_scale_factor(scale_factor).draw_text_number_xy(imgbuf, col, row, text, text_scale_factor)
 }
 
+/// Draws a [LocalCostMatrix] on a default-sized image
 pub fn draw_cost_matrix(imgbuf: &mut OutputImage, cm: LocalCostMatrix, v_min: u8, v_max: u8, b_max: u8, a: u8, skip_out_of_bounds_values: bool) {
   draw_cost_matrix_with_scale_factor(imgbuf, cm, v_min, v_max, b_max, a, DEFAULT_SCALE_FACTOR, skip_out_of_bounds_values)
 }
 
-fn draw_cost_matrix_with_scale_factor(imgbuf: &mut OutputImage, cm: LocalCostMatrix, v_min: u8, v_max: u8, b_max: u8, a: u8, scale_factor: u32, skip_out_of_bounds_values: bool) {
-  let alpha_overlay = get_cost_matrix_alpha_overlay(&cm, imgbuf.width(), imgbuf.height(), scale_factor, v_min, v_max, b_max, a, skip_out_of_bounds_values);
-  image::imageops::overlay(imgbuf, &alpha_overlay, 0, 0);
-
-  for (position, value) in cm.iter() {
-    if value == 0 {
-      continue;
-    }
-
-    if skip_out_of_bounds_values {
-      if value > v_max {
-        continue;
-      }
-
-      if value < v_min {
-        continue;
-      }
-    }
-
-    let col = position.x.u8();
-    let row = position.y.u8();
-
-    let text = value.to_string();
-
-    let text_scale_factor = if value > 9 {
-      ((scale_factor as f32) * 0.75) as u32
-    }
-    else {
-      scale_factor
-    };
-
-    draw_text_number_xy_with_scale_factor(imgbuf, col.into(), row.into(), &text, scale_factor, text_scale_factor);
-  }
+/// Draws a [LocalCostMatrix] on an image with user-supplied scaling
+#[allow(clippy::too_many_arguments)]
+pub fn draw_cost_matrix_with_scale_factor(imgbuf: &mut OutputImage, cm: LocalCostMatrix, v_min: u8, v_max: u8, b_max: u8, a: u8, scale_factor: u32, skip_out_of_bounds_values: bool) {
+  RenderContext::with_scale_factor(scale_factor).draw_cost_matrix(imgbuf, cm, v_min, v_max, b_max, a, skip_out_of_bounds_values)
 }
 
-fn get_cost_matrix_alpha_overlay(cm: &LocalCostMatrix, overlay_width: u32, overlay_height: u32, scale_factor: u32, v_min: u8, v_max: u8, b_max: u8, a: u8, skip_out_of_bounds_values: bool) -> OutputImage {
-  let mut alpha_overlay = image::ImageBuffer::new(overlay_width, overlay_height);
-
-  for (position, value) in cm.iter() {
-    let clamped_value = if value > v_max {
-      if skip_out_of_bounds_values {
-        continue;
-      }
-      v_max
-    }
-    else {
-      if value < v_min {
-        if skip_out_of_bounds_values {
-          continue;
-        }
-        v_min
-      }
-      else {
-        value
-      }
-    };
-
-    let range = (v_max - v_min) as f32;
-
-    let b = b_max - lerp(0.0, b_max as f32, ((clamped_value - v_min) as f32)/range) as u8;
-
-    let others = lerp(b_max as f32, 0.0, (b as f32)/(b_max as f32)) as u8;
-
-    let alpha = if value == 0 {
-      0
-    }
-    else {
-      a
-    };
-
-    let rgba = image::Rgba([others, others, b, alpha]);
-    // let rgba = image::Rgba([0, 0, 255, a]);
-
-    let x = position.x.u8();
-    let y = position.y.u8();
+/// Draws a [LocalCostMatrix] as an overlay colored by a [Colormap]
+pub fn draw_cost_matrix_with_colormap(imgbuf: &mut OutputImage, cm: LocalCostMatrix, v_min: u8, v_max: u8, colormap: &dyn Colormap, a: u8, skip_out_of_bounds_values: bool) {
+  draw_cost_matrix_with_colormap_and_scale_factor(imgbuf, cm, v_min, v_max, colormap, a, DEFAULT_SCALE_FACTOR, skip_out_of_bounds_values)
+}
 
-    let x_start = (x as u32) * scale_factor + 1;
-    let y_start = (y as u32) * scale_factor + 1;
-    let x_end = x_start + scale_factor;
-    let y_end = y_start + scale_factor;
+/// Draws a [LocalCostMatrix] as an overlay colored by a [Colormap] with a user-supplied scaling factor
+#[allow(clippy::too_many_arguments)]
+pub fn draw_cost_matrix_with_colormap_and_scale_factor(imgbuf: &mut OutputImage, cm: LocalCostMatrix, v_min: u8, v_max: u8, colormap: &dyn Colormap, a: u8, scale_factor: u32, skip_out_of_bounds_values: bool) {
+  RenderContext::with_scale_factor(scale_factor).draw_cost_matrix_with_colormap(imgbuf, cm, v_min, v_max, colormap, a, skip_out_of_bounds_values)
+}
 
-    for draw_x in x_start..x_end {
-      for draw_y in y_start..y_end {
-        alpha_overlay.put_pixel(draw_x, draw_y, rgba);
-      }
-    }
-  }
+/// Draws a [LocalCostMatrix] on a default-sized image with user-supplied options
+pub fn draw_cost_matrix_with_options(imgbuf: &mut OutputImage, cm: &LocalCostMatrix, colormap: &dyn Colormap, options: &CostMatrixOptions) {
+  draw_cost_matrix_with_options_and_scale_factor(imgbuf, cm, colormap, options, DEFAULT_SCALE_FACTOR)
+}
 
-  alpha_overlay
+/// Draws a [LocalCostMatrix] on an image with user-supplied options and scaling
+pub fn draw_cost_matrix_with_options_and_scale_factor(imgbuf: &mut OutputImage, cm: &LocalCostMatrix, colormap: &dyn Colormap, options: &CostMatrixOptions, scale_factor: u32) {
+  RenderContext::with_scale_factor(scale_factor).draw_cost_matrix_with_options(imgbuf, cm, colormap, options)
 }
 
 /// Draws a [Terrain] tile at a specific cell location
@@ -394,13 +411,7 @@ pub fn draw_terrain_tile_xy(imgbuf: &mut OutputImage, col: u32, row: u32, tile:
 
 /// Draws a [Terrain] tile at a specific cell location with a user-supplied scaling factor
 pub fn draw_terrain_tile_xy_with_scale_factor(imgbuf: &mut OutputImage, col: u32, row: u32, tile: &Terrain, scale_factor: u32) {
-  let tile_img: &OutputImage = match tile {
-    Terrain::Plain => &assets_data::TERRAIN_PLAIN_IMG,
-    Terrain::Swamp => &assets_data::TERRAIN_SWAMP_IMG,
-    Terrain::Wall  => &assets_data::TERRAIN_WALL_IMG,
-  };
-
-  draw_tile_img_xy(imgbuf, col, row, tile_img, scale_factor);
+  RenderContext::with_scale_factor(scale_factor).draw_terrain_tile_xy(imgbuf, col, row, tile)
 }
 
 /// Draws a [Resource] tile at a specific cell location
@@ -410,19 +421,7 @@ pub fn draw_resource_tile_xy(imgbuf: &mut OutputImage, col: u32, row: u32, tile:
 
 /// Draws a [Resource] tile at a specific cell location with a user-supplied scaling factor
 pub fn draw_resource_tile_xy_with_scale_factor(imgbuf: &mut OutputImage, col: u32, row: u32, tile: &Resource, scale_factor: u32) {
-  let tile_img = match tile {
-    Resource::Source    => &*assets_data::RESOURCE_SOURCE_IMG,
-    Resource::Hydrogen  => &*assets_data::RESOURCE_HYDROGEN_IMG,
-    Resource::Oxygen    => &*assets_data::RESOURCE_OXYGEN_IMG,
-    Resource::Keanium   => &*assets_data::RESOURCE_KEANIUM_IMG,
-    Resource::Lemergium => &*assets_data::RESOURCE_LEMERGIUM_IMG,
-    Resource::Utrium    => &*assets_data::RESOURCE_UTRIUM_IMG,
-    Resource::Zynthium  => &*assets_data::RESOURCE_ZYNTHIUM_IMG,
-    Resource::Catalyst  => &*assets_data::RESOURCE_CATALYST_IMG,
-    Resource::Unknown   => &*assets_data::RESOURCE_UNKNOWN_IMG,
-  };
-
-  draw_tile_img_xy(imgbuf, col, row, &tile_img, scale_factor);
+  RenderContext::with_scale_factor(scale_factor).draw_resource_tile_xy(imgbuf, col, row, tile)
 }
 
 /// Draws a [BuildableStructure] tile at a specific cell location
@@ -432,20 +431,30 @@ pub fn draw_buildablestructure_tile_xy(imgbuf: &mut OutputImage, col: u32, row:
 
 /// Draws a [BuildableStructure] tile at a specific cell location with a user-supplied scaling factor
 pub fn draw_buildablestructure_tile_xy_with_scale_factor(imgbuf: &mut OutputImage, col: u32, row: u32, tile: &BuildableStructure, scale_factor: u32) {
+  RenderContext::with_scale_factor(scale_factor).draw_buildablestructure_tile_xy(imgbuf, col, row, tile)
+}
+
+/// Looks up the sprite used to draw a [BuildableStructure]
+#[allow(clippy::explicit_auto_deref)]
+fn buildablestructure_tile_img(tile: &BuildableStructure) -> &'static OutputImage {
   use BuildableStructure::*;
   use assets_data::*;
 
-  let tile_image = match tile {
+  match tile {
     ConstructedWall => &*STRUCTURE_CONSTRUCTEDWALL_IMG,
     Container       => &*STRUCTURE_CONTAINER_IMG,
     Controller      => &*STRUCTURE_CONTROLLER_IMG,
     Extension       => &*STRUCTURE_EXTENSION_IMG,
     Extractor       => &*STRUCTURE_EXTRACTOR_IMG,
     Factory         => &*STRUCTURE_FACTORY_IMG,
+    InvaderCore     => &*STRUCTURE_INVADERCORE_IMG,
+    KeeperLair      => &*STRUCTURE_KEEPERLAIR_IMG,
     Lab             => &*STRUCTURE_LAB_IMG,
     Link            => &*STRUCTURE_LINK_IMG,
     Nuker           => &*STRUCTURE_NUKER_IMG,
     Observer        => &*STRUCTURE_OBSERVER_IMG,
+    Portal          => &*STRUCTURE_PORTAL_IMG,
+    PowerBank       => &*STRUCTURE_POWERBANK_IMG,
     PowerSpawn      => &*STRUCTURE_POWERSPAWN_IMG,
     Rampart         => &*STRUCTURE_RAMPART_IMG,
     Road            => &*STRUCTURE_ROAD_IMG,
@@ -454,66 +463,64 @@ pub fn draw_buildablestructure_tile_xy_with_scale_factor(imgbuf: &mut OutputImag
     Terminal        => &*STRUCTURE_TERMINAL_IMG,
     Tower           => &*STRUCTURE_TOWER_IMG,
     Unknown         => &*STRUCTURE_UNKNOWN_IMG,
-  };
-  draw_tile_img_xy(imgbuf, col, row, tile_image, scale_factor);
-}
-
-/// Underlying helper function to draw a tile image at a specific cell location
-fn draw_tile_img_xy(imgbuf: &mut OutputImage, col: u32, row: u32, tile_img: &OutputImage, scale_factor: u32) {
-  let new_width = scale_factor;
-  let new_height = scale_factor;
-  let tile_img = if (new_width != tile_img.width()) | (new_height != tile_img.height()) {
-    &image::imageops::resize(tile_img, new_width, new_height, image::imageops::FilterType::Nearest)
-  } else {
-    tile_img
-  };
-
-  let x = (col * scale_factor + 1).try_into().unwrap();
-  let y = (row * scale_factor + 1).try_into().unwrap();
-
-  image::imageops::overlay(imgbuf, tile_img, x, y);
+  }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn get_tile_alpha_overlay(overlay_width: u32, overlay_height: u32, scale_factor: u32, r: u8, g: u8, b: u8, a: u8, x: u8, y: u8) -> OutputImage {
-  let mut alpha_overlay = image::ImageBuffer::new(overlay_width, overlay_height);
-
-  let rgba = image::Rgba([r, g, b, a]);
+  RenderContext::with_scale_factor(scale_factor).get_tile_alpha_overlay(overlay_width, overlay_height, image::Rgba([r, g, b, a]), x, y)
+}
 
-  let x_start = (x as u32) * scale_factor + 1;
-  let y_start = (y as u32) * scale_factor + 1;
-  let x_end = x_start + scale_factor;
-  let y_end = y_start + scale_factor;
+#[allow(clippy::too_many_arguments)]
+pub fn get_tile_alpha_overlay_multi_tile(overlay_width: u32, overlay_height: u32, scale_factor: u32, r: u8, g: u8, b: u8, a: u8, tiles: &[(u8, u8)]) -> OutputImage {
+  RenderContext::with_scale_factor(scale_factor).get_tile_alpha_overlay_multi_tile(overlay_width, overlay_height, image::Rgba([r, g, b, a]), tiles)
+}
 
-  for draw_x in x_start..x_end {
-    for draw_y in y_start..y_end {
-      alpha_overlay.put_pixel(draw_x, draw_y, rgba);
-    }
-  }
+fn lerp(v0: f32, v1: f32, t: f32) -> f32 {
+  (1.0 - t) * v0 + t * v1
+}
 
-  alpha_overlay
+/// Returns a room coordinate, for tests that know it is in bounds
+#[cfg(test)]
+fn xy(x: u8, y: u8) -> screeps::local::RoomXY {
+  screeps::local::RoomXY::checked_new(x, y).expect("test coordinate should be in the room")
 }
 
-pub fn get_tile_alpha_overlay_multi_tile(overlay_width: u32, overlay_height: u32, scale_factor: u32, r: u8, g: u8, b: u8, a: u8, tiles: &[(u8, u8)]) -> OutputImage {
-  let mut alpha_overlay = image::ImageBuffer::new(overlay_width, overlay_height);
+/// Writes an object of a type at a cell in W1N1, as it appears in an offline map dump
+#[cfg(test)]
+fn offline_object_json(object_type: &str, x: u8, y: u8, fields: &str) -> String {
+  format!(r#"{{"type":"{}","_id":"5bbcaa0e9099fc012e630b6f","room":"W1N1","x":{},"y":{}{}}}"#, object_type, x, y, fields)
+}
 
-  let rgba = image::Rgba([r, g, b, a]);
+/// Builds an [OfflineObject] of a type at (10, 10) in W1N1
+#[cfg(test)]
+fn offline_object(object_type: &str, fields: &str) -> OfflineObject {
+  serde_json::from_str(&offline_object_json(object_type, 10, 10, fields)).expect("offline object should deserialize")
+}
 
-  for (x, y) in tiles {
-    let x_start = (*x as u32) * scale_factor + 1;
-    let y_start = (*y as u32) * scale_factor + 1;
-    let x_end = x_start + scale_factor;
-    let y_end = y_start + scale_factor;
+#[cfg(test)]
+mod tests {
+  use super::*;
 
-    for draw_x in x_start..x_end {
-      for draw_y in y_start..y_end {
-        alpha_overlay.put_pixel(draw_x, draw_y, rgba);
-      }
-    }
+  #[test]
+  fn converts_offline_structures() {
+    let lair = offline_object("keeperLair", "");
+    assert_eq!(BuildableStructure::try_from(&lair), Ok(BuildableStructure::KeeperLair));
+    let portal = offline_object("portal", r#","destination":{"room":"W2N2","x":25,"y":25}"#);
+    assert_eq!(BuildableStructure::try_from(&portal), Ok(BuildableStructure::Portal));
+    let controller = offline_object("controller", r#","level":3"#);
+    assert_eq!(BuildableStructure::try_from(controller), Ok(BuildableStructure::Controller));
   }
 
-  alpha_overlay
-}
+  #[test]
+  fn rejects_offline_objects_that_arent_structures() {
+    let source = offline_object("source", r#","energy":3000,"energyCapacity":3000,"ticksToRegeneration":300"#);
+    assert_eq!(BuildableStructure::try_from(&source), Err(ConversionError::NotAStructure));
+    let mineral = offline_object("mineral", r#","density":3,"mineralType":"H","mineralAmount":70000"#);
+    assert_eq!(BuildableStructure::try_from(&mineral), Err(ConversionError::NotAStructure));
 
-fn lerp(v0: f32, v1: f32, t: f32) -> f32 {
-  return (1.0 - t) * v0 + t * v1;
+    let unknown = offline_object("invaderCore", "");
+    assert!(matches!(unknown, OfflineObject::Unknown));
+    assert_eq!(BuildableStructure::try_from(&unknown), Err(ConversionError::UnknownObject));
+  }
 }
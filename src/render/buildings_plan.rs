@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+
+use screeps::constants::structure::StructureType;
+use screeps::local::RoomXY;
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// The controller level assumed for exports that don't give one
+const DEFAULT_RCL: u8 = 8;
+
+/// Errors that can occur when reading a buildings-planner export
+#[derive(Debug)]
+pub enum BuildingsPlanError {
+  /// The data isn't valid JSON in the expected shape
+  Json(serde_json::Error),
+  /// The controller level isn't between 1 and 8
+  InvalidRcl(String),
+  /// A position is outside of the room
+  InvalidPosition { structure_type: StructureType, x: i64, y: i64 },
+}
+
+impl std::fmt::Display for BuildingsPlanError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BuildingsPlanError::Json(e) => write!(f, "invalid buildings plan: {}", e),
+      BuildingsPlanError::InvalidRcl(rcl) => write!(f, "invalid controller level {}", rcl),
+      BuildingsPlanError::InvalidPosition { structure_type, x, y } => write!(f, "{} position ({}, {}) is outside of the room", structure_type, x, y),
+    }
+  }
+}
+
+impl std::error::Error for BuildingsPlanError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      BuildingsPlanError::Json(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<serde_json::Error> for BuildingsPlanError {
+  fn from(e: serde_json::Error) -> Self {
+    BuildingsPlanError::Json(e)
+  }
+}
+
+/// The controller level, which some exports write as a string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RclValue {
+  Number(i64),
+  Text(String),
+}
+
+#[derive(Deserialize)]
+struct BuildingsPlanData {
+  rcl: Option<RclValue>,
+  buildings: BTreeMap<String, BuildingPositions>,
+}
+
+#[derive(Deserialize)]
+struct BuildingPositions {
+  pos: Vec<BuildingPosition>,
+}
+
+#[derive(Deserialize)]
+struct BuildingPosition {
+  x: i64,
+  y: i64,
+}
+
+#[derive(Serialize)]
+struct BuildingsPlanOutput {
+  rcl: u8,
+  buildings: BTreeMap<String, BuildingPositionsOutput>,
+}
+
+#[derive(Serialize)]
+struct BuildingPositionsOutput {
+  pos: Vec<BuildingPositionOutput>,
+}
+
+#[derive(Serialize)]
+struct BuildingPositionOutput {
+  x: u8,
+  y: u8,
+}
+
+impl BasePlan {
+  /// Reads a plan from the buildings-planner JSON format shared by players
+  ///
+  /// The format has one controller level for the whole plan, so each placement
+  /// is given the lowest level at which the game allows that many of its type,
+  /// in the order they are listed. Placements beyond the limit get the plan's
+  /// level, so [BasePlan::overbuilt] reports them. Entries that aren't
+  /// structure types, such as the sources some planners add, are skipped.
+  ///
+  /// Since the levels are guessed, a plan whose placements are built at other
+  /// levels reads back with different levels after [BasePlan::to_buildings_json].
+  pub fn from_buildings_json(json: &str) -> Result<BasePlan, BuildingsPlanError> {
+    let data: BuildingsPlanData = serde_json::from_str(json)?;
+    let rcl = match data.rcl {
+      None => DEFAULT_RCL,
+      Some(RclValue::Number(rcl)) => u8::try_from(rcl).ok().filter(|rcl| (1..=DEFAULT_RCL).contains(rcl))
+        .ok_or_else(|| BuildingsPlanError::InvalidRcl(rcl.to_string()))?,
+      Some(RclValue::Text(rcl)) => rcl.trim().parse::<u8>().ok().filter(|rcl| (1..=DEFAULT_RCL).contains(rcl))
+        .ok_or(BuildingsPlanError::InvalidRcl(rcl))?,
+    };
+
+    let mut plan = BasePlan::new();
+    for (name, positions) in data.buildings {
+      let Ok(structure_type) = name.parse::<StructureType>() else { continue };
+      for (index, BuildingPosition { x, y }) in positions.pos.into_iter().enumerate() {
+        let xy = u8::try_from(x).ok().zip(u8::try_from(y).ok())
+          .and_then(|(x, y)| RoomXY::checked_new(x, y).ok())
+          .ok_or(BuildingsPlanError::InvalidPosition { structure_type, x, y })?;
+        plan = plan.structure(structure_type, xy, min_rcl_for_count(structure_type, index as u32 + 1, rcl));
+      }
+    }
+
+    Ok(plan)
+  }
+
+  /// Writes the placements built by a controller level in the buildings-planner JSON format
+  ///
+  /// The format has no level for each placement, so every [PlannedStructure::min_rcl]
+  /// is lost; only the given level is written. Placements are grouped by type.
+  pub fn to_buildings_json(&self, rcl: u8) -> String {
+    let mut buildings: BTreeMap<String, BuildingPositionsOutput> = BTreeMap::new();
+    for structure in self.at_rcl(rcl) {
+      buildings.entry(structure.structure_type.to_string())
+        .or_insert_with(|| BuildingPositionsOutput { pos: Vec::new() })
+        .pos
+        .push(BuildingPositionOutput { x: structure.xy.x.u8(), y: structure.xy.y.u8() });
+    }
+
+    serde_json::to_string(&BuildingsPlanOutput { rcl, buildings }).expect("buildings plan should serialize")
+  }
+}
+
+/// Returns the lowest controller level allowing a number of structures of a type, or the plan's level if none does
+fn min_rcl_for_count(structure_type: StructureType, count: u32, plan_rcl: u8) -> u8 {
+  if structure_type.controller_structures(DEFAULT_RCL.into()) == 0 {
+    return 1;
+  }
+  (1..=plan_rcl)
+    .find(|rcl| structure_type.controller_structures((*rcl).into()) >= count)
+    .unwrap_or(plan_rcl)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn placements(plan: &BasePlan) -> Vec<(StructureType, RoomXY, u8)> {
+    plan.structures.iter().map(|structure| (structure.structure_type, structure.xy, structure.min_rcl)).collect()
+  }
+
+  #[test]
+  fn hand_built_plan_loses_its_levels() {
+    let plan = BasePlan::new()
+      .structure(StructureType::Tower, xy(20, 20), 3)
+      .structure(StructureType::Extension, xy(24, 24), 6)
+      .structure(StructureType::Spawn, xy(25, 25), 1)
+      .structure(StructureType::Observer, xy(30, 30), 8);
+
+    let read_back = BasePlan::from_buildings_json(&plan.to_buildings_json(8)).unwrap();
+    assert_eq!(placements(&read_back), vec![
+      (StructureType::Extension, xy(24, 24), 2),
+      (StructureType::Observer, xy(30, 30), 8),
+      (StructureType::Spawn, xy(25, 25), 1),
+      (StructureType::Tower, xy(20, 20), 3),
+    ]);
+    assert_ne!(read_back, plan);
+
+    let read_back = BasePlan::from_buildings_json(&plan.to_buildings_json(3)).unwrap();
+    assert_eq!(placements(&read_back), vec![
+      (StructureType::Spawn, xy(25, 25), 1),
+      (StructureType::Tower, xy(20, 20), 3),
+    ]);
+  }
+
+  #[test]
+  fn reads_levels_from_list_order() {
+    let positions: Vec<String> = (0..6).map(|x| format!(r#"{{"x":{},"y":10}}"#, x)).collect();
+    let json = format!(r#"{{"rcl":"3","buildings":{{"extension":{{"pos":[{}]}}}}}}"#, positions.join(","));
+    let plan = BasePlan::from_buildings_json(&json).unwrap();
+    let levels: Vec<u8> = plan.structures.iter().map(|structure| structure.min_rcl).collect();
+    assert_eq!(levels, vec![2, 2, 2, 2, 2, 3]);
+
+    // Placements beyond the limit at the plan's level keep that level
+    let json = json.replace(r#""rcl":"3""#, r#""rcl":2"#);
+    let plan = BasePlan::from_buildings_json(&json).unwrap();
+    assert_eq!(plan.structures[5].min_rcl, 2);
+    assert_eq!(plan.overbuilt(2).len(), 1);
+  }
+
+  #[test]
+  fn reads_string_and_missing_levels() {
+    let buildings = r#""buildings":{"storage":{"pos":[{"x":25,"y":25}]}}"#;
+    let with_rcl = |rcl: &str| BasePlan::from_buildings_json(&format!("{{{}{}}}", rcl, buildings));
+    assert_eq!(with_rcl(r#""rcl":"8","#).unwrap().structures[0].min_rcl, 4);
+    assert_eq!(with_rcl(r#""rcl":" 5 ","#).unwrap().structures[0].min_rcl, 4);
+    assert_eq!(with_rcl("").unwrap().structures[0].min_rcl, 4);
+    assert_eq!(with_rcl(r#""rcl":8,"#).unwrap(), with_rcl(r#""rcl":"8","#).unwrap());
+  }
+
+  #[test]
+  fn rejects_out_of_range_levels() {
+    for rcl in ["0", "9", "-1", "300", r#""0""#, r#""9""#, r#""eight""#] {
+      let json = format!(r#"{{"rcl":{},"buildings":{{}}}}"#, rcl);
+      assert!(matches!(BasePlan::from_buildings_json(&json), Err(BuildingsPlanError::InvalidRcl(_))), "rcl {}", rcl);
+    }
+  }
+
+  #[test]
+  fn rejects_positions_outside_of_the_room() {
+    for (x, y) in [(50, 25), (25, 50), (-1, 25), (25, 300)] {
+      let json = format!(r#"{{"buildings":{{"road":{{"pos":[{{"x":{},"y":{}}}]}}}}}}"#, x, y);
+      match BasePlan::from_buildings_json(&json) {
+        Err(BuildingsPlanError::InvalidPosition { structure_type, x: err_x, y: err_y }) => {
+          assert_eq!((structure_type, err_x, err_y), (StructureType::Road, x, y));
+        },
+        result => panic!("expected an invalid position for ({}, {}), got {:?}", x, y, result),
+      }
+    }
+  }
+
+  #[test]
+  fn skips_entries_that_arent_structures() {
+    let json = r#"{"rcl":8,"buildings":{"source":{"pos":[{"x":10,"y":10}]},"mineral":{"pos":[{"x":60,"y":60}]},"road":{"pos":[{"x":11,"y":10}]}}}"#;
+    let plan = BasePlan::from_buildings_json(json).unwrap();
+    assert_eq!(placements(&plan), vec![(StructureType::Road, xy(11, 10), 1)]);
+  }
+
+  #[test]
+  fn rejects_malformed_json() {
+    assert!(matches!(BasePlan::from_buildings_json("{"), Err(BuildingsPlanError::Json(_))));
+    assert!(matches!(BasePlan::from_buildings_json(r#"{"rcl":8}"#), Err(BuildingsPlanError::Json(_))));
+  }
+}
@@ -0,0 +1,389 @@
+use screeps::constants::Part;
+use screeps::local::{LocalRoomTerrain, Position, RoomName, RoomXY};
+
+use super::*;
+
+/// Errors that can occur when drawing on a [RoomCanvas]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanvasError {
+  /// The cell is outside of the room dimensions of the canvas
+  OutOfBounds { col: u32, row: u32 },
+  /// The position is in a different room than the one the canvas shows
+  WrongRoom { expected: RoomName, found: RoomName },
+  /// The room is outside of the region a world canvas shows
+  OutsideRegion { room_name: RoomName },
+  /// The room canvas was not given a room name, so it can't be placed on a world canvas
+  MissingRoomName,
+}
+
+impl std::fmt::Display for CanvasError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      CanvasError::OutOfBounds { col, row } => write!(f, "cell ({}, {}) is outside of the canvas", col, row),
+      CanvasError::WrongRoom { expected, found } => write!(f, "position is in room {}, but the canvas shows room {}", found, expected),
+      CanvasError::OutsideRegion { room_name } => write!(f, "room {} is outside of the canvas region", room_name),
+      CanvasError::MissingRoomName => write!(f, "the room canvas has no room name"),
+    }
+  }
+}
+
+impl std::error::Error for CanvasError {}
+
+/// A location that can be resolved to a cell on a [RoomCanvas]
+pub trait CanvasLocation {
+  /// The column and row of the cell
+  fn cell(&self) -> (u32, u32);
+
+  /// The room the location is in, if it carries one
+  fn room_name(&self) -> Option<RoomName> {
+    None
+  }
+}
+
+impl CanvasLocation for RoomXY {
+  fn cell(&self) -> (u32, u32) {
+    (self.x.u8().into(), self.y.u8().into())
+  }
+}
+
+impl CanvasLocation for Position {
+  fn cell(&self) -> (u32, u32) {
+    self.xy().cell()
+  }
+
+  fn room_name(&self) -> Option<RoomName> {
+    Some(Position::room_name(*self))
+  }
+}
+
+impl CanvasLocation for (u8, u8) {
+  fn cell(&self) -> (u32, u32) {
+    (self.0.into(), self.1.into())
+  }
+}
+
+/// An image of a single room that is drawn on using room coordinates
+///
+/// Every drawing method checks that the location is on the canvas (and, if the
+/// canvas was given a room name, that a [Position] is in that room) and returns
+/// a [CanvasError] instead of panicking.
+#[derive(Debug, Clone)]
+pub struct RoomCanvas {
+  ctx: RenderContext,
+  room_name: Option<RoomName>,
+  imgbuf: OutputImage,
+}
+
+impl Default for RoomCanvas {
+  fn default() -> Self {
+    Self::new(RenderContext::default())
+  }
+}
+
+impl RoomCanvas {
+  /// Creates a blank canvas sized for the given context
+  pub fn new(ctx: RenderContext) -> Self {
+    Self {
+      imgbuf: ctx.create_image(),
+      ctx,
+      room_name: None,
+    }
+  }
+
+  /// Restricts the canvas to a single room, so [Position]s in other rooms are rejected
+  pub fn with_room_name(mut self, room_name: RoomName) -> Self {
+    self.room_name = Some(room_name);
+    self
+  }
+
+  /// The context used for all drawing on this canvas
+  pub fn context(&self) -> &RenderContext {
+    &self.ctx
+  }
+
+  /// The room this canvas is restricted to, if any
+  pub fn room_name(&self) -> Option<RoomName> {
+    self.room_name
+  }
+
+  /// Borrows the underlying image
+  pub fn image(&self) -> &OutputImage {
+    &self.imgbuf
+  }
+
+  /// Mutably borrows the underlying image, for drawing that this type does not cover
+  pub fn image_mut(&mut self) -> &mut OutputImage {
+    &mut self.imgbuf
+  }
+
+  /// Consumes the canvas, returning the underlying image
+  pub fn into_image(self) -> OutputImage {
+    self.imgbuf
+  }
+
+  /// Resolves a location to a cell, checking that it is on this canvas
+  pub fn cell<L: CanvasLocation>(&self, location: &L) -> Result<(u32, u32), CanvasError> {
+    if let (Some(expected), Some(found)) = (self.room_name, location.room_name()) {
+      if expected != found {
+        return Err(CanvasError::WrongRoom { expected, found });
+      }
+    }
+
+    let (col, row) = location.cell();
+    if (col >= self.ctx.room_max_cols) | (row >= self.ctx.room_max_rows) {
+      return Err(CanvasError::OutOfBounds { col, row });
+    }
+
+    Ok((col, row))
+  }
+
+  /// Checks that every location is on this canvas, so nothing is drawn unless all of them are
+  fn check_cells<'a, L: CanvasLocation + 'a>(&self, locations: impl IntoIterator<Item = &'a L>) -> Result<(), CanvasError> {
+    locations.into_iter().try_for_each(|location| self.cell(location).map(|_| ()))
+  }
+
+  /// Draws the cell grid over the canvas
+  pub fn draw_grid(&mut self) {
+    self.ctx.draw_grid(&mut self.imgbuf);
+  }
+
+  /// Draws a [Terrain] tile
+  pub fn draw_terrain<L: CanvasLocation>(&mut self, location: &L, tile: &Terrain) -> Result<(), CanvasError> {
+    let (col, row) = self.cell(location)?;
+    self.ctx.draw_terrain_tile_xy(&mut self.imgbuf, col, row, tile);
+    Ok(())
+  }
+
+  /// Draws a [Resource] tile
+  pub fn draw_resource<L: CanvasLocation>(&mut self, location: &L, tile: &Resource) -> Result<(), CanvasError> {
+    let (col, row) = self.cell(location)?;
+    self.ctx.draw_resource_tile_xy(&mut self.imgbuf, col, row, tile);
+    Ok(())
+  }
+
+  /// Draws a [BuildableStructure] tile
+  pub fn draw_structure<L: CanvasLocation>(&mut self, location: &L, tile: &BuildableStructure) -> Result<(), CanvasError> {
+    let (col, row) = self.cell(location)?;
+    self.ctx.draw_buildablestructure_tile_xy(&mut self.imgbuf, col, row, tile);
+    Ok(())
+  }
+
+  /// Draws any [RenderableObject]
+  pub fn draw_object<L: CanvasLocation>(&mut self, location: &L, object: &RenderableObject) -> Result<(), CanvasError> {
+    let (col, row) = self.cell(location)?;
+    self.ctx.draw_object_xy(&mut self.imgbuf, col, row, object);
+    Ok(())
+  }
+
+  /// Draws a creep with its body part ring
+  pub fn draw_creep<L: CanvasLocation>(&mut self, location: &L, body: &[Part], owner_color: image::Rgba<u8>) -> Result<(), CanvasError> {
+    let (col, row) = self.cell(location)?;
+    self.ctx.draw_creep_xy(&mut self.imgbuf, col, row, body, owner_color);
+    Ok(())
+  }
+
+  /// Draws text centered in a cell, scaled down to fit
+  pub fn draw_text<L: CanvasLocation>(&mut self, location: &L, text: &str) -> Result<(), CanvasError> {
+    let (col, row) = self.cell(location)?;
+    self.ctx.draw_centered_text_number_xy(&mut self.imgbuf, col, row, text);
+    Ok(())
+  }
+
+  /// Blends a solid color over a cell
+  pub fn fill<L: CanvasLocation>(&mut self, location: &L, rgba: image::Rgba<u8>) -> Result<(), CanvasError> {
+    let (col, row) = self.cell(location)?;
+    self.ctx.blend_cell(&mut self.imgbuf, col, row, rgba);
+    Ok(())
+  }
+
+  /// Draws a [LocalCostMatrix] over the canvas; see [RenderContext::draw_cost_matrix]
+  pub fn draw_cost_matrix(&mut self, cm: LocalCostMatrix, v_min: u8, v_max: u8, b_max: u8, a: u8, skip_out_of_bounds_values: bool) {
+    self.ctx.draw_cost_matrix(&mut self.imgbuf, cm, v_min, v_max, b_max, a, skip_out_of_bounds_values);
+  }
+
+  /// Draws a [LocalCostMatrix] over the canvas using a [Colormap]; see [RenderContext::draw_cost_matrix_with_colormap]
+  pub fn draw_cost_matrix_with_colormap(&mut self, cm: LocalCostMatrix, v_min: u8, v_max: u8, colormap: &dyn Colormap, a: u8, skip_out_of_bounds_values: bool) {
+    self.ctx.draw_cost_matrix_with_colormap(&mut self.imgbuf, cm, v_min, v_max, colormap, a, skip_out_of_bounds_values);
+  }
+
+  /// Draws a [LocalCostMatrix] over the canvas with user-supplied options; see [RenderContext::draw_cost_matrix_with_options]
+  pub fn draw_cost_matrix_with_options(&mut self, cm: &LocalCostMatrix, colormap: &dyn Colormap, options: &CostMatrixOptions) {
+    self.ctx.draw_cost_matrix_with_options(&mut self.imgbuf, cm, colormap, options);
+  }
+
+  /// Draws a scalar field as a heatmap over the canvas; see [RenderContext::draw_heatmap]
+  pub fn draw_heatmap(&mut self, source: &dyn HeatmapSource, colormap: &dyn Colormap, options: &HeatmapOptions) -> Option<(f64, f64)> {
+    self.ctx.draw_heatmap(&mut self.imgbuf, source, colormap, options)
+  }
+
+  /// Shades every tile within range of a tile; see [RenderContext::draw_range]
+  pub fn draw_range(&mut self, center: RoomXY, range: u8, rgba: image::Rgba<u8>) -> Result<(), CanvasError> {
+    self.cell(&center)?;
+    self.ctx.draw_range(&mut self.imgbuf, center, range, rgba);
+    Ok(())
+  }
+
+  /// Shades every tile within range of any of several tiles; see [RenderContext::draw_ranges]
+  pub fn draw_ranges(&mut self, ranges: &[(RoomXY, u8)], rgba: image::Rgba<u8>) -> Result<(), CanvasError> {
+    self.check_cells(ranges.iter().map(|(center, _)| center))?;
+    self.ctx.draw_ranges(&mut self.imgbuf, ranges, rgba);
+    Ok(())
+  }
+
+  /// Draws the combined strength of area effect sources; see [RenderContext::draw_range_sources]
+  pub fn draw_range_sources(&mut self, sources: &[RangeSource], colormap: &dyn Colormap, options: &HeatmapOptions) -> Result<Option<(f64, f64)>, CanvasError> {
+    self.check_cells(sources.iter().map(|source| &source.center))?;
+    Ok(self.ctx.draw_range_sources(&mut self.imgbuf, sources, colormap, options))
+  }
+
+  /// Draws the combined tower strength at every tile; see [RenderContext::draw_tower_effectiveness]
+  pub fn draw_tower_effectiveness(&mut self, towers: &[RoomXY], action: TowerAction, colormap: &dyn Colormap, options: &HeatmapOptions) -> Result<Option<(f64, f64)>, CanvasError> {
+    self.check_cells(towers)?;
+    Ok(self.ctx.draw_tower_effectiveness(&mut self.imgbuf, towers, action, colormap, options))
+  }
+
+  /// Draws the structures of a plan built by a controller level; see [RenderContext::draw_base_plan]
+  pub fn draw_base_plan(&mut self, plan: &BasePlan, rcl: u8, terrain: Option<&LocalRoomTerrain>) -> Result<Vec<OverbuiltStructure>, CanvasError> {
+    self.check_cells(plan.at_rcl(rcl).map(|structure| &structure.xy))?;
+    Ok(self.ctx.draw_base_plan(&mut self.imgbuf, plan, rcl, terrain))
+  }
+
+  /// Draws a connected road network; see [RenderContext::draw_roads]
+  pub fn draw_roads(&mut self, roads: &[RoomXY], terrain: Option<&LocalRoomTerrain>, style: &RoadStyle) -> Result<(), CanvasError> {
+    self.check_cells(roads)?;
+    self.ctx.draw_roads(&mut self.imgbuf, roads, terrain, style);
+    Ok(())
+  }
+
+  /// Draws a whole room's terrain with smooth contours; see [RenderContext::draw_room_terrain]
+  pub fn draw_room_terrain(&mut self, terrain: &LocalRoomTerrain, style: &TerrainStyle) {
+    self.ctx.draw_room_terrain(&mut self.imgbuf, terrain, style);
+  }
+
+  /// Draws structures in layers, with ramparts over the rest; see [RenderContext::draw_structure_stacks]
+  pub fn draw_structure_stacks(&mut self, structures: &[(RoomXY, BuildableStructure)], terrain: Option<&LocalRoomTerrain>, style: &StructureStackStyle) -> Result<(), CanvasError> {
+    self.check_cells(structures.iter().map(|(xy, _)| xy))?;
+    self.ctx.draw_structure_stacks(&mut self.imgbuf, structures, terrain, style);
+    Ok(())
+  }
+
+  /// Draws ramparts as a translucent cover with a merged outline; see [RenderContext::draw_ramparts]
+  pub fn draw_ramparts(&mut self, ramparts: &[RoomXY], style: &StructureStackStyle) -> Result<(), CanvasError> {
+    self.check_cells(ramparts)?;
+    self.ctx.draw_ramparts(&mut self.imgbuf, ramparts, style);
+    Ok(())
+  }
+
+  /// Widens the canvas with a side panel on the right that shows a legend; see [RenderContext::append_legend_panel]
+  ///
+  /// Cells keep their positions, so drawing can continue after the panel is added.
+  pub fn append_legend_panel(&mut self, legend: &Legend) {
+    self.imgbuf = self.ctx.append_legend_panel(&self.imgbuf, legend);
+  }
+
+  /// Draws a circle at a fractional room coordinate; see [RenderContext::draw_circle]
+  pub fn draw_circle(&mut self, x: f32, y: f32, style: &CircleStyle) {
+    self.ctx.draw_circle(&mut self.imgbuf, x, y, style);
+  }
+
+  /// Draws a line between fractional room coordinates; see [RenderContext::draw_line]
+  pub fn draw_line(&mut self, from: (f32, f32), to: (f32, f32), style: &LineStyle) {
+    self.ctx.draw_line(&mut self.imgbuf, from, to, style);
+  }
+
+  /// Draws a rectangle at a fractional room coordinate; see [RenderContext::draw_rect]
+  pub fn draw_rect(&mut self, x: f32, y: f32, width: f32, height: f32, style: &RectStyle) {
+    self.ctx.draw_rect(&mut self.imgbuf, x, y, width, height, style);
+  }
+
+  /// Draws a polygon through fractional room coordinates; see [RenderContext::draw_poly]
+  pub fn draw_poly(&mut self, points: &[(f32, f32)], style: &PolyStyle) {
+    self.ctx.draw_poly(&mut self.imgbuf, points, style);
+  }
+
+  /// Draws styled text at a fractional room coordinate; see [RenderContext::draw_text]
+  pub fn draw_visual_text(&mut self, text: &str, x: f32, y: f32, style: &TextStyle) {
+    self.ctx.draw_text(&mut self.imgbuf, text, x, y, style);
+  }
+
+  /// Draws any [Visual]
+  pub fn draw_visual(&mut self, visual: &Visual) {
+    self.ctx.draw_visual(&mut self.imgbuf, visual);
+  }
+
+  /// Parses serialized room visuals and draws them in order; see [RenderContext::replay_visuals]
+  pub fn replay_visuals(&mut self, data: &str) -> Result<(), VisualParseError> {
+    self.ctx.replay_visuals(&mut self.imgbuf, data)
+  }
+
+  /// Draws a path through a list of steps; see [RenderContext::draw_path]
+  pub fn draw_path(&mut self, path: &[RoomXY], style: &PathStyle) -> Result<(), CanvasError> {
+    self.check_cells(path)?;
+    self.ctx.draw_path(&mut self.imgbuf, path, style);
+    Ok(())
+  }
+
+  /// Draws a path through a list of positions, like the result of `PathFinder.search`
+  ///
+  /// If the canvas was given a room name, only the steps in that room are drawn,
+  /// with each stretch of the path that passes through it drawn separately.
+  /// Otherwise every step must be in the same room as the first, since there is
+  /// no way to tell which room the canvas shows.
+  pub fn draw_position_path(&mut self, path: &[Position], style: &PathStyle) -> Result<(), CanvasError> {
+    let Some(room_name) = self.room_name else {
+      if let Some(first) = path.first() {
+        let expected = Position::room_name(*first);
+        if let Some(other) = path.iter().find(|position| Position::room_name(**position) != expected) {
+          return Err(CanvasError::WrongRoom { expected, found: Position::room_name(*other) });
+        }
+      }
+      self.check_cells(path)?;
+      self.ctx.draw_position_path(&mut self.imgbuf, path, style);
+      return Ok(());
+    };
+    let stretches: Vec<&[Position]> = path.split(|position| Position::room_name(*position) != room_name).collect();
+    self.check_cells(stretches.iter().flat_map(|stretch| stretch.iter()))?;
+    for stretch in stretches {
+      self.ctx.draw_position_path(&mut self.imgbuf, stretch, style);
+    }
+    Ok(())
+  }
+
+  /// Draws a path serialized by `Room.serializePath`; see [parse_serialized_path]
+  pub fn draw_serialized_path(&mut self, path: &str, style: &PathStyle) -> Result<(), PathParseError> {
+    self.ctx.draw_serialized_path(&mut self.imgbuf, path, style)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use screeps::local::RoomCoordinate;
+
+  use super::*;
+
+  fn position(x: u8, y: u8, room: &str) -> Position {
+    Position::new(RoomCoordinate::new(x).unwrap(), RoomCoordinate::new(y).unwrap(), room.parse().unwrap())
+  }
+
+  #[test]
+  fn position_path_without_a_room_must_stay_in_one_room() {
+    let ctx = RenderContext::with_scale_factor(2);
+    let path = [position(48, 10, "W1N1"), position(49, 10, "W1N1"), position(0, 10, "E0N1")];
+    let mut canvas = RoomCanvas::new(ctx);
+    let expected = CanvasError::WrongRoom { expected: "W1N1".parse().unwrap(), found: "E0N1".parse().unwrap() };
+    assert_eq!(canvas.draw_position_path(&path, &PathStyle::default()), Err(expected));
+    assert!(canvas.image() == &ctx.create_image());
+
+    assert_eq!(canvas.draw_position_path(&path[..2], &PathStyle::default()), Ok(()));
+    assert!(canvas.image() != &ctx.create_image());
+  }
+
+  #[test]
+  fn position_path_with_a_room_draws_only_its_steps() {
+    let path = [position(48, 10, "W1N1"), position(49, 10, "W1N1"), position(0, 10, "E0N1"), position(1, 10, "E0N1")];
+    let mut canvas = RoomCanvas::new(RenderContext::with_scale_factor(2)).with_room_name("E0N1".parse().unwrap());
+    assert_eq!(canvas.draw_position_path(&path, &PathStyle::default()), Ok(()));
+    let (x, _) = canvas.context().room_to_pixel(48.5, 10.0);
+    assert!(canvas.image().enumerate_pixels().all(|(px, _, pixel)| (px as f32) < x || *pixel == canvas.context().theme.background));
+  }
+}
@@ -0,0 +1,273 @@
+use super::*;
+
+/// Maps normalized values to colors, for per-tile numeric overlays
+///
+/// Colormaps are `Debug` so that a [Legend] holding one can be printed.
+pub trait Colormap: std::fmt::Debug {
+  /// Returns the color for a value between 0.0 and 1.0; values outside that range are clamped
+  fn color(&self, t: f64) -> image::Rgba<u8>;
+
+  /// Returns the color for a value within a range, normalizing it first
+  fn color_in_range(&self, value: f64, min: f64, max: f64) -> image::Rgba<u8> {
+    let t = if max > min { (value - min) / (max - min) } else { 0.0 };
+    self.color(t)
+  }
+}
+
+impl<C: Colormap + ?Sized> Colormap for &C {
+  fn color(&self, t: f64) -> image::Rgba<u8> {
+    (**self).color(t)
+  }
+}
+
+impl<C: Colormap + ?Sized> Colormap for Box<C> {
+  fn color(&self, t: f64) -> image::Rgba<u8> {
+    (**self).color(t)
+  }
+}
+
+/// Samples of matplotlib's viridis map at even intervals
+const VIRIDIS_STOPS: [[u8; 3]; 9] = [
+  [68, 1, 84], [71, 45, 123], [59, 82, 139], [44, 114, 142], [33, 145, 140],
+  [40, 174, 128], [94, 201, 98], [173, 220, 48], [253, 231, 37],
+];
+
+/// Samples of matplotlib's magma map at even intervals
+const MAGMA_STOPS: [[u8; 3]; 9] = [
+  [0, 0, 4], [28, 16, 68], [79, 18, 123], [129, 37, 129], [181, 54, 122],
+  [229, 80, 100], [251, 135, 97], [254, 194, 135], [252, 253, 191],
+];
+
+/// Samples of matplotlib's plasma map at even intervals
+const PLASMA_STOPS: [[u8; 3]; 9] = [
+  [13, 8, 135], [76, 2, 161], [126, 3, 168], [169, 35, 149], [204, 71, 120],
+  [229, 107, 93], [248, 149, 64], [253, 197, 39], [240, 249, 33],
+];
+
+/// Samples of ColorBrewer's red-blue diverging map at even intervals, from blue to red
+const RED_BLUE_STOPS: [[u8; 3]; 9] = [
+  [33, 102, 172], [67, 147, 195], [146, 197, 222], [209, 229, 240], [247, 247, 247],
+  [253, 219, 199], [244, 165, 130], [214, 96, 77], [178, 24, 43],
+];
+
+/// The colors of matplotlib's tab10 categorical map
+const TAB10_COLORS: [[u8; 3]; 10] = [
+  [31, 119, 180], [255, 127, 14], [44, 160, 44], [214, 39, 40], [148, 103, 189],
+  [140, 86, 75], [227, 119, 194], [127, 127, 127], [188, 189, 34], [23, 190, 207],
+];
+
+/// The perceptually uniform viridis map, from dark purple to yellow
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Viridis;
+
+impl Colormap for Viridis {
+  fn color(&self, t: f64) -> image::Rgba<u8> {
+    interpolate_evenly_spaced(&VIRIDIS_STOPS, t)
+  }
+}
+
+/// The perceptually uniform magma map, from black through purple to pale yellow
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Magma;
+
+impl Colormap for Magma {
+  fn color(&self, t: f64) -> image::Rgba<u8> {
+    interpolate_evenly_spaced(&MAGMA_STOPS, t)
+  }
+}
+
+/// The perceptually uniform plasma map, from dark blue through pink to yellow
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Plasma;
+
+impl Colormap for Plasma {
+  fn color(&self, t: f64) -> image::Rgba<u8> {
+    interpolate_evenly_spaced(&PLASMA_STOPS, t)
+  }
+}
+
+/// A diverging map from blue through white to red, for values around a midpoint
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RedBlue;
+
+impl Colormap for RedBlue {
+  fn color(&self, t: f64) -> image::Rgba<u8> {
+    interpolate_evenly_spaced(&RED_BLUE_STOPS, t)
+  }
+}
+
+/// A map of distinct colors, splitting the value range into one equal bin per color
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Categorical {
+  colors: Vec<image::Rgba<u8>>,
+}
+
+impl Default for Categorical {
+  fn default() -> Self {
+    Self::new(TAB10_COLORS.iter().map(|[r, g, b]| image::Rgba([*r, *g, *b, 255])).collect())
+  }
+}
+
+impl Categorical {
+  /// Creates a categorical map from a list of colors; an empty list yields black
+  pub fn new(colors: Vec<image::Rgba<u8>>) -> Self {
+    Self { colors }
+  }
+
+  /// Returns the color of a category by index, wrapping around when there are more categories than colors
+  pub fn category_color(&self, index: usize) -> image::Rgba<u8> {
+    match self.colors.len() {
+      0 => image::Rgba([0, 0, 0, 255]),
+      len => self.colors[index % len],
+    }
+  }
+
+  /// The number of colors in the map
+  pub fn len(&self) -> usize {
+    self.colors.len()
+  }
+
+  /// Whether the map has no colors
+  pub fn is_empty(&self) -> bool {
+    self.colors.is_empty()
+  }
+}
+
+impl Colormap for Categorical {
+  fn color(&self, t: f64) -> image::Rgba<u8> {
+    let index = (clamp_unit(t) * self.colors.len() as f64) as usize;
+    self.category_color(index.min(self.colors.len().saturating_sub(1)))
+  }
+}
+
+/// A user-defined map that interpolates linearly between colors at given positions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+  stops: Vec<(f64, image::Rgba<u8>)>,
+}
+
+impl Gradient {
+  /// Creates a gradient from `(position, color)` stops, with positions between 0.0 and 1.0
+  ///
+  /// Stops may be given in any order; values before the first or after the last stop take its color.
+  pub fn new(mut stops: Vec<(f64, image::Rgba<u8>)>) -> Self {
+    stops.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    Self { stops }
+  }
+
+  /// Creates a gradient from colors spaced evenly between 0.0 and 1.0
+  pub fn evenly_spaced(colors: &[image::Rgba<u8>]) -> Self {
+    let last = colors.len().saturating_sub(1).max(1) as f64;
+    Self::new(colors.iter().enumerate().map(|(i, color)| (i as f64 / last, *color)).collect())
+  }
+
+  /// The `(position, color)` stops of the gradient, sorted by position
+  pub fn stops(&self) -> &[(f64, image::Rgba<u8>)] {
+    &self.stops
+  }
+}
+
+impl Colormap for Gradient {
+  fn color(&self, t: f64) -> image::Rgba<u8> {
+    let t = clamp_unit(t);
+    let Some(&(first_position, first_color)) = self.stops.first() else {
+      return image::Rgba([0, 0, 0, 255]);
+    };
+    if t <= first_position {
+      return first_color;
+    }
+
+    for pair in self.stops.windows(2) {
+      let ((start, from), (end, to)) = (pair[0], pair[1]);
+      if t <= end {
+        let local = if end > start { (t - start) / (end - start) } else { 1.0 };
+        return mix(from, to, local);
+      }
+    }
+
+    self.stops[self.stops.len() - 1].1
+  }
+}
+
+/// Clamps a value to the range 0.0 to 1.0, treating NaN as 0.0
+fn clamp_unit(t: f64) -> f64 {
+  if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) }
+}
+
+/// Interpolates between opaque colors spaced evenly between 0.0 and 1.0
+fn interpolate_evenly_spaced(stops: &[[u8; 3]], t: f64) -> image::Rgba<u8> {
+  let position = clamp_unit(t) * (stops.len() - 1) as f64;
+  let index = (position as usize).min(stops.len() - 2);
+  let [r1, g1, b1] = stops[index];
+  let [r2, g2, b2] = stops[index + 1];
+  mix(image::Rgba([r1, g1, b1, 255]), image::Rgba([r2, g2, b2, 255]), position - index as f64)
+}
+
+/// Linearly interpolates between two colors, including alpha
+fn mix(from: image::Rgba<u8>, to: image::Rgba<u8>, t: f64) -> image::Rgba<u8> {
+  let channel = |i: usize| lerp(from[i] as f32, to[i] as f32, t as f32).round() as u8;
+  image::Rgba([channel(0), channel(1), channel(2), channel(3)])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const BLACK: image::Rgba<u8> = image::Rgba([0, 0, 0, 255]);
+  const WHITE: image::Rgba<u8> = image::Rgba([255, 255, 255, 255]);
+  const RED: image::Rgba<u8> = image::Rgba([255, 0, 0, 255]);
+
+  #[test]
+  fn gradient_sorts_its_stops() {
+    let gradient = Gradient::new(vec![(1.0, WHITE), (0.0, BLACK), (0.5, RED)]);
+    let positions: Vec<f64> = gradient.stops().iter().map(|(position, _)| *position).collect();
+    assert_eq!(positions, vec![0.0, 0.5, 1.0]);
+    assert_eq!(gradient.color(0.5), RED);
+    assert_eq!(gradient.color(0.25), image::Rgba([128, 0, 0, 255]));
+    assert_eq!(gradient.color(0.75), image::Rgba([255, 128, 128, 255]));
+  }
+
+  #[test]
+  fn gradient_clamps_outside_its_range() {
+    let gradient = Gradient::new(vec![(0.2, BLACK), (0.8, WHITE)]);
+    assert_eq!(gradient.color(-1.0), BLACK);
+    assert_eq!(gradient.color(0.1), BLACK);
+    assert_eq!(gradient.color(0.9), WHITE);
+    assert_eq!(gradient.color(2.0), WHITE);
+    assert_eq!(gradient.color(f64::NAN), BLACK);
+    assert_eq!(Gradient::new(Vec::new()).color(0.5), BLACK);
+  }
+
+  #[test]
+  fn categorical_bins_values() {
+    let categorical = Categorical::new(vec![BLACK, RED, WHITE]);
+    assert_eq!(categorical.color(0.0), BLACK);
+    assert_eq!(categorical.color(0.34), RED);
+    assert_eq!(categorical.color(0.99), WHITE);
+    assert_eq!(categorical.color(1.0), WHITE);
+    assert_eq!(categorical.color(5.0), WHITE);
+    assert_eq!(categorical.category_color(4), RED);
+  }
+
+  #[test]
+  fn empty_categorical_is_black() {
+    let categorical = Categorical::new(Vec::new());
+    assert!(categorical.is_empty());
+    assert_eq!(categorical.color(0.0), BLACK);
+    assert_eq!(categorical.color(1.0), BLACK);
+    assert_eq!(categorical.category_color(3), BLACK);
+  }
+
+  #[test]
+  fn builtin_maps_treat_nan_as_the_start() {
+    let maps: [&dyn Colormap; 4] = [&Viridis, &Magma, &Plasma, &RedBlue];
+    for map in maps {
+      assert_eq!(map.color(f64::NAN), map.color(0.0));
+      assert_eq!(map.color(-1.0), map.color(0.0));
+      assert_eq!(map.color(2.0), map.color(1.0));
+    }
+    assert_eq!(Viridis.color(0.0), image::Rgba([68, 1, 84, 255]));
+    assert_eq!(Viridis.color(1.0), image::Rgba([253, 231, 37, 255]));
+    assert_eq!(RedBlue.color(0.5), image::Rgba([247, 247, 247, 255]));
+  }
+}
@@ -0,0 +1,245 @@
+use super::*;
+
+/// The colors used for the parts of an image that aren't drawn from sprites
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+  /// The color a new image is filled with
+  pub background: image::Rgba<u8>,
+  /// The color of the cell grid lines
+  pub grid: image::Rgba<u8>,
+  /// The color of text drawn in cells
+  pub text: image::Rgba<u8>,
+  /// The color of the lines between rooms on world maps
+  pub room_border: image::Rgba<u8>,
+  /// The color used to flag problems, such as structures over the controller limit
+  pub error: image::Rgba<u8>,
+}
+
+impl Default for Theme {
+  fn default() -> Self {
+    Self {
+      background: image::Rgba([0, 0, 0, 255]),
+      grid: image::Rgba([255, 255, 255, 128]),
+      text: image::Rgba([255, 255, 255, 255]),
+      room_border: image::Rgba([255, 255, 255, 160]),
+      error: image::Rgba([255, 32, 32, 140]),
+    }
+  }
+}
+
+/// Carries the layout and styling parameters shared by every drawing operation
+///
+/// All of the free drawing functions in this module are shorthands for the
+/// methods here using a default context with the given scale factor. Using a
+/// single context for an image keeps tiles, overlays and grid lines aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderContext {
+  /// The number of pixels allocated for each room cell
+  pub scale_factor: u32,
+  /// The number of columns in a room (x-coordinate)
+  pub room_max_cols: u32,
+  /// The number of rows in a room (y-coordinate)
+  pub room_max_rows: u32,
+  /// The width in pixels of the grid lines between cells
+  pub border_width: u32,
+  /// The colors used for backgrounds, grid lines and text
+  pub theme: Theme,
+}
+
+impl Default for RenderContext {
+  fn default() -> Self {
+    Self {
+      scale_factor: DEFAULT_SCALE_FACTOR,
+      room_max_cols: DEFAULT_ROOM_MAX_COLUMNS,
+      room_max_rows: DEFAULT_ROOM_MAX_ROWS,
+      border_width: 1,
+      theme: Theme::default(),
+    }
+  }
+}
+
+impl RenderContext {
+  /// Creates a default context with a user-supplied scaling factor
+  pub fn with_scale_factor(scale_factor: u32) -> Self {
+    Self {
+      scale_factor,
+      ..Default::default()
+    }
+  }
+
+  /// The width in pixels of images created by this context
+  pub fn image_width(&self) -> u32 {
+    self.room_max_cols * self.scale_factor + self.border_width
+  }
+
+  /// The height in pixels of images created by this context
+  pub fn image_height(&self) -> u32 {
+    self.room_max_rows * self.scale_factor + self.border_width
+  }
+
+  /// The pixel location of the top-left corner of a cell's contents
+  pub fn cell_origin(&self, col: u32, row: u32) -> (u32, u32) {
+    (col * self.scale_factor + self.border_width, row * self.scale_factor + self.border_width)
+  }
+
+  /// Creates an image sized for this context, filled with the theme background
+  pub fn create_image(&self) -> OutputImage {
+    image::ImageBuffer::from_pixel(self.image_width(), self.image_height(), self.theme.background)
+  }
+
+  /// Draws the cell grid on an image
+  ///
+  /// Only the room's cells are covered, so anything beyond them, such as a
+  /// legend panel, is left untouched.
+  pub fn draw_grid(&self, imgbuf: &mut OutputImage) {
+    let scale_factor = self.scale_factor;
+    let width = self.image_width().min(imgbuf.width());
+    let height = self.image_height().min(imgbuf.height());
+    for y in 0..height {
+      for x in 0..width {
+        if (x % scale_factor < self.border_width) | (y % scale_factor < self.border_width) {
+          imgbuf.put_pixel(x, y, self.theme.grid);
+        }
+      }
+    }
+  }
+
+  /// Draws centered text at a specific cell location, scaling the text down to fit
+  pub fn draw_centered_text_number_xy(&self, imgbuf: &mut OutputImage, col: u32, row: u32, text: &str) {
+    self.draw_centered_text_number_xy_with_color(imgbuf, col, row, text, self.theme.text)
+  }
+
+  /// Draws centered text in a given color at a specific cell location, scaling the text down to fit
+  pub fn draw_centered_text_number_xy_with_color(&self, imgbuf: &mut OutputImage, col: u32, row: u32, text: &str, color: image::Rgba<u8>) {
+    let font = &assets_data::FREE_MONO_FONT;
+    let cell_size = self.scale_factor;
+    // we want some borders between text, so we need to define an area
+    // we'll draw the text within.
+    let padding = (cell_size / 25).max(1);
+    let text_area = cell_size.saturating_sub(2 * padding).max(1);
+    let (scale, width, height) = calculate_centered_text_scale(font, text_area, text);
+    let x_offset = text_area.saturating_sub(width) / 2;
+    let y_offset = text_area.saturating_sub(height) / 2;
+    let (x, y) = self.cell_origin(col, row);
+    let x = (x + padding + x_offset) as i32;
+    let y = (y + padding + y_offset) as i32;
+    imageproc::drawing::draw_text_mut(imgbuf, color, x, y, scale, font, text);
+  }
+
+  /// Draws text at the top-left of a specific cell location, with the given text height in pixels
+  pub fn draw_text_number_xy(&self, imgbuf: &mut OutputImage, col: u32, row: u32, text: &str, text_scale_factor: u32) {
+    let (x, y) = self.cell_origin(col, row);
+    let scale = rusttype::Scale::uniform(text_scale_factor as f32);
+    let font = &assets_data::FREE_MONO_FONT;
+    imageproc::drawing::draw_text_mut(imgbuf, self.theme.text, (x + 1) as i32, (y + 1) as i32, scale, font, text);
+  }
+
+  /// Draws a [Terrain] tile at a specific cell location
+  pub fn draw_terrain_tile_xy(&self, imgbuf: &mut OutputImage, col: u32, row: u32, tile: &Terrain) {
+    let tile_img: &OutputImage = match tile {
+      Terrain::Plain => &assets_data::TERRAIN_PLAIN_IMG,
+      Terrain::Swamp => &assets_data::TERRAIN_SWAMP_IMG,
+      Terrain::Wall  => &assets_data::TERRAIN_WALL_IMG,
+    };
+
+    self.draw_tile_img_xy(imgbuf, col, row, tile_img);
+  }
+
+  /// Draws a [Resource] tile at a specific cell location
+  pub fn draw_resource_tile_xy(&self, imgbuf: &mut OutputImage, col: u32, row: u32, tile: &Resource) {
+    let tile_img = match tile {
+      Resource::Other(resource_type) => {
+        self.draw_resource_type_icon_xy(imgbuf, col, row, *resource_type);
+        return;
+      },
+      Resource::Source    => &*assets_data::RESOURCE_SOURCE_IMG,
+      Resource::Hydrogen  => &*assets_data::RESOURCE_HYDROGEN_IMG,
+      Resource::Oxygen    => &*assets_data::RESOURCE_OXYGEN_IMG,
+      Resource::Keanium   => &*assets_data::RESOURCE_KEANIUM_IMG,
+      Resource::Lemergium => &*assets_data::RESOURCE_LEMERGIUM_IMG,
+      Resource::Utrium    => &*assets_data::RESOURCE_UTRIUM_IMG,
+      Resource::Zynthium  => &*assets_data::RESOURCE_ZYNTHIUM_IMG,
+      Resource::Catalyst  => &*assets_data::RESOURCE_CATALYST_IMG,
+      Resource::Unknown   => &*assets_data::RESOURCE_UNKNOWN_IMG,
+    };
+
+    self.draw_tile_img_xy(imgbuf, col, row, tile_img);
+  }
+
+  /// Draws a [BuildableStructure] tile at a specific cell location
+  pub fn draw_buildablestructure_tile_xy(&self, imgbuf: &mut OutputImage, col: u32, row: u32, tile: &BuildableStructure) {
+    self.draw_tile_img_xy(imgbuf, col, row, buildablestructure_tile_img(tile));
+  }
+
+  /// Creates a transparent overlay sized for the given image, with a single cell filled in
+  pub fn get_tile_alpha_overlay(&self, overlay_width: u32, overlay_height: u32, rgba: image::Rgba<u8>, x: u8, y: u8) -> OutputImage {
+    self.get_tile_alpha_overlay_multi_tile(overlay_width, overlay_height, rgba, &[(x, y)])
+  }
+
+  /// Creates a transparent overlay sized for the given image, with several cells filled in
+  pub fn get_tile_alpha_overlay_multi_tile(&self, overlay_width: u32, overlay_height: u32, rgba: image::Rgba<u8>, tiles: &[(u8, u8)]) -> OutputImage {
+    let mut alpha_overlay = image::ImageBuffer::new(overlay_width, overlay_height);
+
+    for (x, y) in tiles {
+      self.fill_cell(&mut alpha_overlay, *x, *y, rgba);
+    }
+
+    alpha_overlay
+  }
+
+  /// Returns black or white, whichever stands out more against the center pixel of a cell
+  pub(super) fn contrasting_text_color(&self, imgbuf: &OutputImage, col: u32, row: u32) -> image::Rgba<u8> {
+    let (x, y) = self.cell_origin(col, row);
+    let center = imgbuf.get_pixel_checked(x + self.scale_factor / 2, y + self.scale_factor / 2);
+    let [r, g, b, _] = center.copied().unwrap_or(self.theme.background).0;
+    let luminance = 0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32;
+    if luminance > 140.0 {
+      image::Rgba([0, 0, 0, 255])
+    } else {
+      image::Rgba([255, 255, 255, 255])
+    }
+  }
+
+  /// Underlying helper function to fill a cell's contents with a solid color
+  pub(super) fn fill_cell(&self, imgbuf: &mut OutputImage, x: u8, y: u8, rgba: image::Rgba<u8>) {
+    let (x_start, y_start) = self.cell_origin(x.into(), y.into());
+    let x_end = (x_start + self.scale_factor).min(imgbuf.width());
+    let y_end = (y_start + self.scale_factor).min(imgbuf.height());
+
+    for draw_x in x_start..x_end {
+      for draw_y in y_start..y_end {
+        imgbuf.put_pixel(draw_x, draw_y, rgba);
+      }
+    }
+  }
+
+  /// Underlying helper function to blend a color over a cell's contents
+  pub(super) fn blend_cell(&self, imgbuf: &mut OutputImage, col: u32, row: u32, rgba: image::Rgba<u8>) {
+    use image::Pixel;
+
+    let (x_start, y_start) = self.cell_origin(col, row);
+    let x_end = (x_start + self.scale_factor).min(imgbuf.width());
+    let y_end = (y_start + self.scale_factor).min(imgbuf.height());
+
+    for draw_x in x_start..x_end {
+      for draw_y in y_start..y_end {
+        imgbuf.get_pixel_mut(draw_x, draw_y).blend(&rgba);
+      }
+    }
+  }
+
+  /// Underlying helper function to draw a tile image at a specific cell location
+  pub(super) fn draw_tile_img_xy(&self, imgbuf: &mut OutputImage, col: u32, row: u32, tile_img: &OutputImage) {
+    let new_width = self.scale_factor;
+    let new_height = self.scale_factor;
+    let tile_img = if (new_width != tile_img.width()) | (new_height != tile_img.height()) {
+      &image::imageops::resize(tile_img, new_width, new_height, image::imageops::FilterType::Nearest)
+    } else {
+      tile_img
+    };
+
+    let (x, y) = self.cell_origin(col, row);
+
+    image::imageops::overlay(imgbuf, tile_img, x.into(), y.into());
+  }
+}
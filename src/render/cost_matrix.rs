@@ -0,0 +1,176 @@
+use screeps::local::RoomXY;
+
+use super::*;
+use super::visual::rgba_to_hex;
+
+/// Options that control how [RenderContext::draw_cost_matrix_with_options] draws a [LocalCostMatrix]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostMatrixOptions {
+  /// The value mapped to the start of the colormap
+  pub v_min: u8,
+  /// The value mapped to the end of the colormap
+  pub v_max: u8,
+  /// The opacity of the colored cells
+  pub a: u8,
+  /// Whether to leave values outside of `v_min..=v_max` undrawn instead of clamping them
+  pub skip_out_of_bounds_values: bool,
+  /// Whether to write each cell's value in it
+  pub draw_labels: bool,
+  /// Whether to mark cells with a value of 255 (impassable) with a cross instead of digits
+  pub wall_glyph: bool,
+}
+
+impl Default for CostMatrixOptions {
+  fn default() -> Self {
+    Self {
+      v_min: 0,
+      v_max: u8::MAX,
+      a: 192,
+      skip_out_of_bounds_values: false,
+      draw_labels: true,
+      wall_glyph: false,
+    }
+  }
+}
+
+impl RenderContext {
+  /// Draws a [LocalCostMatrix] as a blue-to-yellow overlay with the value in each cell
+  ///
+  /// `b_max` is the brightest channel value used by the gradient.
+  #[allow(clippy::too_many_arguments)]
+  pub fn draw_cost_matrix(&self, imgbuf: &mut OutputImage, cm: LocalCostMatrix, v_min: u8, v_max: u8, b_max: u8, a: u8, skip_out_of_bounds_values: bool) {
+    let colormap = Gradient::new(vec![
+      (0.0, image::Rgba([0, 0, b_max, 255])),
+      (1.0, image::Rgba([b_max, b_max, 0, 255])),
+    ]);
+    self.draw_cost_matrix_with_colormap(imgbuf, cm, v_min, v_max, &colormap, a, skip_out_of_bounds_values)
+  }
+
+  /// Draws a [LocalCostMatrix] as an overlay colored by a [Colormap], with the value in each cell
+  ///
+  /// Values are mapped from the range `v_min..=v_max`, and cells with a value of 0 are left uncolored.
+  #[allow(clippy::too_many_arguments)]
+  pub fn draw_cost_matrix_with_colormap(&self, imgbuf: &mut OutputImage, cm: LocalCostMatrix, v_min: u8, v_max: u8, colormap: &dyn Colormap, a: u8, skip_out_of_bounds_values: bool) {
+    let options = CostMatrixOptions {
+      v_min,
+      v_max,
+      a,
+      skip_out_of_bounds_values,
+      ..Default::default()
+    };
+    self.draw_cost_matrix_with_options(imgbuf, &cm, colormap, &options)
+  }
+
+  /// Draws a [LocalCostMatrix] as an overlay colored by a [Colormap], with user-supplied options
+  ///
+  /// Labels are centered in their cells, shrunk to fit, and drawn in black or white
+  /// depending on which stands out more against the colored cell.
+  pub fn draw_cost_matrix_with_options(&self, imgbuf: &mut OutputImage, cm: &LocalCostMatrix, colormap: &dyn Colormap, options: &CostMatrixOptions) {
+    let CostMatrixOptions { v_min, v_max, skip_out_of_bounds_values, .. } = *options;
+    let is_drawn = |value: u8| {
+      let out_of_bounds = (value < v_min) | (value > v_max);
+      (value != 0) & !(skip_out_of_bounds_values & out_of_bounds)
+    };
+
+    let source = |xy: RoomXY| Some(cm.get(xy)).filter(|value| is_drawn(*value)).map(f64::from);
+    let heatmap_options = HeatmapOptions {
+      range: Some((v_min as f64, v_max as f64)),
+      alpha: options.a,
+      ..Default::default()
+    };
+    self.draw_heatmap(imgbuf, &source, colormap, &heatmap_options);
+
+    if !options.draw_labels {
+      return;
+    }
+
+    for (position, value) in cm.iter() {
+      if !is_drawn(value) {
+        continue;
+      }
+
+      let (col, row) = (position.x.u8(), position.y.u8());
+      let color = self.contrasting_text_color(imgbuf, col.into(), row.into());
+
+      if options.wall_glyph & (value == u8::MAX) {
+        self.draw_wall_glyph(imgbuf, col, row, color);
+      } else {
+        self.draw_centered_text_number_xy_with_color(imgbuf, col.into(), row.into(), &value.to_string(), color);
+      }
+    }
+  }
+
+  /// Draws a cross over a cell, marking it as impassable
+  fn draw_wall_glyph(&self, imgbuf: &mut OutputImage, col: u8, row: u8, color: image::Rgba<u8>) {
+    let (x, y) = (col as f32, row as f32);
+    let style = LineStyle::default()
+      .width(0.1)
+      .color(&rgba_to_hex(color))
+      .opacity(1.0);
+    self.draw_line(imgbuf, (x - 0.3, y - 0.3), (x + 0.3, y + 0.3), &style);
+    self.draw_line(imgbuf, (x - 0.3, y + 0.3), (x + 0.3, y - 0.3), &style);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const BLACK: image::Rgba<u8> = image::Rgba([0, 0, 0, 255]);
+  const WHITE: image::Rgba<u8> = image::Rgba([255, 255, 255, 255]);
+
+  /// Draws a cost matrix with a single cell set, over a solid overlay color, returning the image without and with labels
+  fn draw_single_cell(value: u8, overlay: image::Rgba<u8>, wall_glyph: bool) -> (RenderContext, OutputImage, OutputImage) {
+    let ctx = RenderContext::with_scale_factor(20);
+    let mut cm = LocalCostMatrix::new();
+    cm.set(RoomXY::checked_new(10, 10).unwrap(), value);
+    let colormap = Gradient::new(vec![(0.0, overlay)]);
+    let options = CostMatrixOptions { a: 255, wall_glyph, ..Default::default() };
+
+    let mut unlabelled = ctx.create_image();
+    ctx.draw_cost_matrix_with_options(&mut unlabelled, &cm, &colormap, &CostMatrixOptions { draw_labels: false, ..options.clone() });
+    let mut labelled = ctx.create_image();
+    ctx.draw_cost_matrix_with_options(&mut labelled, &cm, &colormap, &options);
+    (ctx, unlabelled, labelled)
+  }
+
+  #[test]
+  fn impassable_cells_get_a_glyph_instead_of_digits() {
+    let (ctx, unlabelled, labelled) = draw_single_cell(u8::MAX, WHITE, true);
+    let mut glyph = unlabelled.clone();
+    ctx.draw_wall_glyph(&mut glyph, 10, 10, BLACK);
+    assert!(labelled == glyph);
+
+    let mut digits = unlabelled;
+    ctx.draw_centered_text_number_xy_with_color(&mut digits, 10, 10, "255", BLACK);
+    assert!(labelled != digits);
+  }
+
+  #[test]
+  fn labels_on_light_cells_are_dark_and_centered() {
+    let (ctx, unlabelled, labelled) = draw_single_cell(100, WHITE, false);
+    let mut expected = unlabelled;
+    ctx.draw_centered_text_number_xy_with_color(&mut expected, 10, 10, "100", BLACK);
+    assert!(labelled == expected);
+
+    // The inked pixels are centered in the cell, give or take a pixel of glyph bearing
+    let (x0, y0) = ctx.cell_origin(10, 10);
+    let inked: Vec<(u32, u32)> = (x0..x0 + 20)
+      .flat_map(|x| (y0..y0 + 20).map(move |y| (x, y)))
+      .filter(|(x, y)| *labelled.get_pixel(*x, *y) != WHITE)
+      .collect();
+    let (min_x, max_x) = (inked.iter().map(|p| p.0).min().unwrap(), inked.iter().map(|p| p.0).max().unwrap());
+    let (min_y, max_y) = (inked.iter().map(|p| p.1).min().unwrap(), inked.iter().map(|p| p.1).max().unwrap());
+    let center = (x0 as f32 + 10.0, y0 as f32 + 10.0);
+    assert!(((min_x + max_x + 1) as f32 / 2.0 - center.0).abs() <= 1.5);
+    assert!(((min_y + max_y + 1) as f32 / 2.0 - center.1).abs() <= 1.5);
+  }
+
+  #[test]
+  fn labels_on_dark_cells_are_light() {
+    let (ctx, unlabelled, labelled) = draw_single_cell(100, BLACK, false);
+    let mut expected = unlabelled;
+    ctx.draw_centered_text_number_xy_with_color(&mut expected, 10, 10, "100", WHITE);
+    assert!(labelled == expected);
+  }
+}
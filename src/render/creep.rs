@@ -0,0 +1,144 @@
+use screeps::constants::Part;
+
+use super::*;
+
+/// The order in which body part segments are laid out around a creep's ring
+const BODY_PART_DRAW_ORDER: [Part; 8] = [
+  Part::Tough,
+  Part::Work,
+  Part::Carry,
+  Part::Attack,
+  Part::RangedAttack,
+  Part::Heal,
+  Part::Claim,
+  Part::Move,
+];
+
+/// The color used for a creep's body when no owner color is given
+pub const DEFAULT_CREEP_OWNER_COLOR: image::Rgba<u8> = image::Rgba([255, 255, 255, 255]);
+
+/// Returns the color the official client uses for a body part type
+pub fn body_part_color(part: Part) -> image::Rgba<u8> {
+  match part {
+    Part::Move         => image::Rgba([169, 183, 198, 255]),
+    Part::Work         => image::Rgba([255, 229, 109, 255]),
+    Part::Carry        => image::Rgba([119, 119, 119, 255]),
+    Part::Attack       => image::Rgba([249, 56, 66, 255]),
+    Part::RangedAttack => image::Rgba([93, 128, 178, 255]),
+    Part::Tough        => image::Rgba([255, 255, 255, 255]),
+    Part::Heal         => image::Rgba([101, 253, 98, 255]),
+    Part::Claim        => image::Rgba([185, 156, 251, 255]),
+    _                  => image::Rgba([0, 0, 0, 255]),
+  }
+}
+
+/// Draws a creep at a specific cell location
+///
+/// The creep's body is filled with the owner color and surrounded by a ring split
+/// into one segment per body part, grouped by part type.
+pub fn draw_creep_xy(imgbuf: &mut OutputImage, col: u32, row: u32, body: &[Part], owner_color: image::Rgba<u8>) {
+  draw_creep_xy_with_scale_factor(imgbuf, col, row, body, owner_color, DEFAULT_SCALE_FACTOR)
+}
+
+/// Draws a creep at a specific cell location with a user-supplied scaling factor
+pub fn draw_creep_xy_with_scale_factor(imgbuf: &mut OutputImage, col: u32, row: u32, body: &[Part], owner_color: image::Rgba<u8>, scale_factor: u32) {
+  RenderContext::with_scale_factor(scale_factor).draw_creep_xy(imgbuf, col, row, body, owner_color)
+}
+
+impl RenderContext {
+  /// Draws a creep at a specific cell location
+  ///
+  /// The creep's body is filled with the owner color and surrounded by a ring split
+  /// into one segment per body part, grouped by part type.
+  pub fn draw_creep_xy(&self, imgbuf: &mut OutputImage, col: u32, row: u32, body: &[Part], owner_color: image::Rgba<u8>) {
+    let scale_factor = self.scale_factor;
+    let mut segments: Vec<Part> = Vec::with_capacity(body.len());
+    for part_type in BODY_PART_DRAW_ORDER {
+      segments.extend(body.iter().filter(|part| **part == part_type));
+    }
+
+    let outer_radius = scale_factor as f32 * 0.4;
+    let ring_width = (scale_factor as f32 * 0.1).max(1.0);
+    let inner_radius = outer_radius - ring_width;
+    let body_radius = inner_radius * 0.7;
+
+    let (x_start, y_start) = self.cell_origin(col, row);
+    let center = scale_factor as f32 / 2.0;
+
+    for dx in 0..scale_factor {
+      for dy in 0..scale_factor {
+        let (x, y) = (x_start + dx, y_start + dy);
+        if (x >= imgbuf.width()) | (y >= imgbuf.height()) {
+          continue;
+        }
+
+        let offset_x = dx as f32 + 0.5 - center;
+        let offset_y = dy as f32 + 0.5 - center;
+        let distance = (offset_x * offset_x + offset_y * offset_y).sqrt();
+
+        let color = if distance <= body_radius {
+          owner_color
+        } else if distance <= inner_radius {
+          image::Rgba([34, 34, 34, 255])
+        } else if distance <= outer_radius {
+          if segments.is_empty() {
+            image::Rgba([85, 85, 85, 255])
+          } else {
+            // Angle measured clockwise from straight up, in the range [0, 1)
+            let turn = (offset_x.atan2(-offset_y) / std::f32::consts::TAU).rem_euclid(1.0);
+            let index = ((turn * segments.len() as f32) as usize).min(segments.len() - 1);
+            body_part_color(segments[index])
+          }
+        } else {
+          continue;
+        };
+
+        imgbuf.put_pixel(x, y, color);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Returns the ring colors a quarter turn apart, starting an eighth of a turn clockwise from straight up
+  fn ring_quarters(body: &[Part]) -> Vec<image::Rgba<u8>> {
+    let ctx = RenderContext::with_scale_factor(20);
+    let mut imgbuf = ctx.create_image();
+    ctx.draw_creep_xy(&mut imgbuf, 5, 5, body, DEFAULT_CREEP_OWNER_COLOR);
+    let (x0, y0) = ctx.cell_origin(5, 5);
+    // Halfway through the ring, which runs from 6 to 8 pixels out at this scale
+    let radius = 7.0;
+    (0..4).map(|quarter| {
+      let angle = (quarter as f32 + 0.5) / 4.0 * std::f32::consts::TAU;
+      let x = x0 as f32 + 10.0 + radius * angle.sin();
+      let y = y0 as f32 + 10.0 - radius * angle.cos();
+      *imgbuf.get_pixel(x as u32, y as u32)
+    }).collect()
+  }
+
+  #[test]
+  fn draws_one_segment_per_part() {
+    let colors = ring_quarters(&[Part::Move, Part::Work, Part::Move, Part::Tough]);
+    let expected: Vec<image::Rgba<u8>> = [Part::Tough, Part::Work, Part::Move, Part::Move].into_iter().map(body_part_color).collect();
+    assert_eq!(colors, expected);
+  }
+
+  #[test]
+  fn draws_an_empty_ring_without_parts() {
+    assert_eq!(ring_quarters(&[]), vec![image::Rgba([85, 85, 85, 255]); 4]);
+  }
+
+  #[test]
+  fn draws_converted_creeps_with_their_body() {
+    let ctx = RenderContext::with_scale_factor(20);
+    let body = vec![Part::Attack, Part::Heal];
+    let mut expected = ctx.create_image();
+    ctx.draw_creep_xy(&mut expected, 3, 4, &body, DEFAULT_CREEP_OWNER_COLOR);
+    let mut drawn = ctx.create_image();
+    ctx.draw_object_xy(&mut drawn, 3, 4, &RenderableObject::Creep(body));
+    assert!(drawn == expected);
+  }
+}
@@ -0,0 +1,108 @@
+use screeps::local::RoomXY;
+
+use super::*;
+
+/// A per-cell scalar field that can be drawn as a heatmap
+///
+/// Cells without a value are left uncolored.
+pub trait HeatmapSource {
+  /// Returns the value of a cell, if it has one
+  fn value(&self, xy: RoomXY) -> Option<f64>;
+}
+
+impl<F: Fn(RoomXY) -> Option<f64>> HeatmapSource for F {
+  fn value(&self, xy: RoomXY) -> Option<f64> {
+    self(xy)
+  }
+}
+
+/// Grids are indexed in row-major order, like room terrain: `y * 50 + x`
+impl<T: Copy + Into<f64>> HeatmapSource for [T; ROOM_AREA] {
+  fn value(&self, xy: RoomXY) -> Option<f64> {
+    Some(self[screeps::local::xy_to_terrain_index(xy)].into())
+  }
+}
+
+/// Options that control how [RenderContext::draw_heatmap] draws a scalar field
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeatmapOptions {
+  /// The values mapped to the ends of the colormap; if unset, the smallest and largest values are used
+  pub range: Option<(f64, f64)>,
+  /// The opacity of the colored cells
+  pub alpha: u8,
+  /// Whether to write each cell's value in it
+  pub draw_labels: bool,
+  /// The number of decimal places in labels; if unset, whole numbers get none and others get one
+  pub label_precision: Option<usize>,
+  /// Whether to widen the image with a side panel holding a colorbar of the range; see [RenderContext::append_legend_panel]
+  pub draw_legend: bool,
+}
+
+impl Default for HeatmapOptions {
+  fn default() -> Self {
+    Self {
+      range: None,
+      alpha: 192,
+      draw_labels: false,
+      label_precision: None,
+      draw_legend: false,
+    }
+  }
+}
+
+/// Draws a scalar field as a heatmap on a default-sized image
+pub fn draw_heatmap(imgbuf: &mut OutputImage, source: &dyn HeatmapSource, colormap: &dyn Colormap, options: &HeatmapOptions) -> Option<(f64, f64)> {
+  draw_heatmap_with_scale_factor(imgbuf, source, colormap, options, DEFAULT_SCALE_FACTOR)
+}
+
+/// Draws a scalar field as a heatmap on an image with user-supplied scaling
+pub fn draw_heatmap_with_scale_factor(imgbuf: &mut OutputImage, source: &dyn HeatmapSource, colormap: &dyn Colormap, options: &HeatmapOptions, scale_factor: u32) -> Option<(f64, f64)> {
+  RenderContext::with_scale_factor(scale_factor).draw_heatmap(imgbuf, source, colormap, options)
+}
+
+impl RenderContext {
+  /// Draws a scalar field as a heatmap, coloring each cell that has a value
+  ///
+  /// Returns the value range that was mapped onto the colormap, or `None` if no cell had a value.
+  /// If a legend is drawn, the image is replaced by a wider one with the legend
+  /// panel on the right; cells keep their positions.
+  pub fn draw_heatmap(&self, imgbuf: &mut OutputImage, source: &dyn HeatmapSource, colormap: &dyn Colormap, options: &HeatmapOptions) -> Option<(f64, f64)> {
+    let values: Vec<(RoomXY, f64)> = (0..ROOM_AREA)
+      .map(screeps::local::terrain_index_to_xy)
+      .filter(|xy| (u32::from(xy.x.u8()) < self.room_max_cols) & (u32::from(xy.y.u8()) < self.room_max_rows))
+      .filter_map(|xy| source.value(xy).filter(|value| value.is_finite()).map(|value| (xy, value)))
+      .collect();
+
+    let (min, max) = match options.range {
+      Some(range) => range,
+      None => values.iter().fold(None, |range, (_, value)| match range {
+        None => Some((*value, *value)),
+        Some((min, max)) => Some((value.min(min), value.max(max))),
+      })?,
+    };
+
+    let mut alpha_overlay = image::ImageBuffer::new(imgbuf.width(), imgbuf.height());
+    for (xy, value) in &values {
+      let [r, g, b, a] = colormap.color_in_range(*value, min, max).0;
+      let alpha = (a as u32 * options.alpha as u32 / 255) as u8;
+      self.fill_cell(&mut alpha_overlay, xy.x.u8(), xy.y.u8(), image::Rgba([r, g, b, alpha]));
+    }
+    image::imageops::overlay(imgbuf, &alpha_overlay, 0, 0);
+
+    if options.draw_labels {
+      let all_whole = values.iter().all(|(_, value)| value.fract() == 0.0);
+      let precision = options.label_precision.unwrap_or(if all_whole { 0 } else { 1 });
+      for (xy, value) in &values {
+        let text = format!("{:.*}", precision, value);
+        let color = self.contrasting_text_color(imgbuf, xy.x.u8().into(), xy.y.u8().into());
+        self.draw_centered_text_number_xy_with_color(imgbuf, xy.x.u8().into(), xy.y.u8().into(), &text, color);
+      }
+    }
+
+    if options.draw_legend {
+      *imgbuf = self.append_legend_panel(imgbuf, &Legend::new().colorbar(None, colormap, min, max));
+    }
+
+    Some((min, max))
+  }
+}
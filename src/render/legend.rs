@@ -0,0 +1,233 @@
+use super::*;
+
+/// One entry in a [Legend]
+#[derive(Debug)]
+pub enum LegendItem<'a> {
+  /// A vertical colorbar for a [Colormap], with the maximum at the top
+  Colorbar {
+    label: Option<String>,
+    colormap: &'a dyn Colormap,
+    min: f64,
+    max: f64,
+    /// The number of evenly spaced tick labels, including both ends
+    ticks: usize,
+  },
+  /// A solid color square with a label
+  Swatch { label: String, color: image::Rgba<u8> },
+  /// An object's icon with a label, such as a structure or resource
+  Object { label: String, object: RenderableObject },
+}
+
+/// A titled list of colorbars and labelled swatches and icons, drawn in a panel beside an image
+#[derive(Debug, Default)]
+pub struct Legend<'a> {
+  pub title: Option<String>,
+  pub items: Vec<LegendItem<'a>>,
+}
+
+impl<'a> Legend<'a> {
+  /// Creates an empty legend
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets the title drawn at the top of the legend
+  pub fn title(mut self, title: &str) -> Self {
+    self.title = Some(title.to_string());
+    self
+  }
+
+  /// Adds a colorbar with five tick labels
+  pub fn colorbar(mut self, label: Option<&str>, colormap: &'a dyn Colormap, min: f64, max: f64) -> Self {
+    self.items.push(LegendItem::Colorbar { label: label.map(str::to_string), colormap, min, max, ticks: 5 });
+    self
+  }
+
+  /// Adds a labelled color swatch
+  pub fn swatch(mut self, label: &str, color: image::Rgba<u8>) -> Self {
+    self.items.push(LegendItem::Swatch { label: label.to_string(), color });
+    self
+  }
+
+  /// Adds a labelled object icon
+  pub fn object(mut self, label: &str, object: RenderableObject) -> Self {
+    self.items.push(LegendItem::Object { label: label.to_string(), object });
+    self
+  }
+}
+
+/// Returns a copy of an image widened with a side panel that shows a legend, using the default theme
+pub fn append_legend_panel(imgbuf: &OutputImage, legend: &Legend) -> OutputImage {
+  RenderContext::default().append_legend_panel(imgbuf, legend)
+}
+
+/// The height of a colorbar, in text lines
+const COLORBAR_LINES: u32 = 8;
+
+impl RenderContext {
+  /// Returns a copy of an image widened with a side panel on the right that shows a legend
+  ///
+  /// Text is sized relative to the image height, so the panel stays readable at any scale.
+  pub fn append_legend_panel(&self, imgbuf: &OutputImage, legend: &Legend) -> OutputImage {
+    let font = &assets_data::FREE_MONO_FONT;
+    let unit = (imgbuf.height() / 40).max(12);
+    let margin = unit;
+    let line_height = unit * 3 / 2;
+    let text_scale = rusttype::Scale::uniform(unit as f32);
+    let title_scale = rusttype::Scale::uniform(unit as f32 * 1.3);
+    let text_width = |scale: rusttype::Scale, text: &str| imageproc::drawing::text_size(scale, font, text).0.max(0) as u32;
+
+    // Measure every row so the panel is wide enough for the longest one and tall enough for all of them
+    let mut content_width = unit * 6;
+    let mut content_height = 2 * margin;
+    if let Some(title) = &legend.title {
+      content_width = content_width.max(text_width(title_scale, title));
+      content_height += line_height * 3 / 2;
+    }
+    for item in &legend.items {
+      content_height += match item {
+        LegendItem::Colorbar { label, .. } => {
+          let label_height = if label.is_some() { line_height } else { 0 };
+          label_height + colorbar_height(unit, line_height)
+        },
+        LegendItem::Swatch { .. } | LegendItem::Object { .. } => line_height,
+      };
+      let width = match item {
+        LegendItem::Colorbar { label, min, max, ticks, .. } => {
+          let tick_width = colorbar_ticks(*min, *max, *ticks).iter()
+            .map(|(_, text)| text_width(text_scale, text))
+            .max()
+            .unwrap_or(0);
+          let label_width = label.as_deref().map_or(0, |label| text_width(text_scale, label));
+          label_width.max(unit * 2 + tick_width)
+        },
+        LegendItem::Swatch { label, .. } | LegendItem::Object { label, .. } => unit * 2 + text_width(text_scale, label),
+      };
+      content_width = content_width.max(width);
+    }
+    let panel_width = content_width + 2 * margin;
+
+    let height = imgbuf.height().max(content_height);
+    let mut output = image::ImageBuffer::from_pixel(imgbuf.width() + panel_width, height, self.theme.background);
+    image::imageops::replace(&mut output, imgbuf, 0, 0);
+    let left = imgbuf.width() + margin;
+    for y in 0..output.height() {
+      for x in imgbuf.width()..(imgbuf.width() + self.border_width.max(1)).min(output.width()) {
+        output.put_pixel(x, y, self.theme.grid);
+      }
+    }
+
+    let mut y = margin;
+    if let Some(title) = &legend.title {
+      imageproc::drawing::draw_text_mut(&mut output, self.theme.text, left as i32, y as i32, title_scale, font, title);
+      y += line_height * 3 / 2;
+    }
+
+    for item in &legend.items {
+      match item {
+        LegendItem::Colorbar { label, colormap, min, max, ticks } => {
+          if let Some(label) = label {
+            imageproc::drawing::draw_text_mut(&mut output, self.theme.text, left as i32, y as i32, text_scale, font, label);
+            y += line_height;
+          }
+
+          // Leave half a line above and below the bar so the end tick labels fit
+          let bar_top = y + unit / 2;
+          let bar_height = line_height * COLORBAR_LINES;
+          for dy in 0..bar_height {
+            let color = colormap.color(1.0 - dy as f64 / (bar_height - 1).max(1) as f64);
+            for dx in 0..unit {
+              if let Some(pixel) = output.get_pixel_mut_checked(left + dx, bar_top + dy) {
+                *pixel = color;
+              }
+            }
+          }
+
+          for (t, text) in colorbar_ticks(*min, *max, *ticks) {
+            let tick_y = bar_top + ((1.0 - t) * (bar_height - 1) as f64) as u32;
+            for dx in unit..(unit + unit / 3) {
+              if let Some(pixel) = output.get_pixel_mut_checked(left + dx, tick_y) {
+                *pixel = self.theme.text;
+              }
+            }
+            let text_y = tick_y as i32 - unit as i32 / 2;
+            imageproc::drawing::draw_text_mut(&mut output, self.theme.text, (left + unit * 3 / 2) as i32, text_y, text_scale, font, &text);
+          }
+
+          y += colorbar_height(unit, line_height);
+        },
+        LegendItem::Swatch { label, color } => {
+          let rect = imageproc::rect::Rect::at(left as i32, y as i32).of_size(unit, unit);
+          imageproc::drawing::draw_filled_rect_mut(&mut output, rect, *color);
+          imageproc::drawing::draw_text_mut(&mut output, self.theme.text, (left + unit * 2) as i32, y as i32, text_scale, font, label);
+          y += line_height;
+        },
+        LegendItem::Object { label, object } => {
+          let icon_ctx = RenderContext {
+            scale_factor: unit,
+            room_max_cols: 1,
+            room_max_rows: 1,
+            border_width: 0,
+            ..*self
+          };
+          let mut icon = image::ImageBuffer::new(unit, unit);
+          icon_ctx.draw_object_xy(&mut icon, 0, 0, object);
+          image::imageops::overlay(&mut output, &icon, left.into(), y.into());
+          imageproc::drawing::draw_text_mut(&mut output, self.theme.text, (left + unit * 2) as i32, y as i32, text_scale, font, label);
+          y += line_height;
+        },
+      }
+    }
+
+    output
+  }
+}
+
+/// Returns the height a colorbar takes up in the panel, including the space around the bar
+fn colorbar_height(unit: u32, line_height: u32) -> u32 {
+  // Room above and below the bar for the end tick labels, then a gap before the next item
+  unit / 2 + line_height * COLORBAR_LINES + unit / 2 + line_height / 2
+}
+
+/// Returns evenly spaced `(position, label)` ticks from the bottom (0.0) to the top (1.0) of a colorbar
+fn colorbar_ticks(min: f64, max: f64, ticks: usize) -> Vec<(f64, String)> {
+  let ticks = ticks.max(2);
+  (0..ticks)
+    .map(|i| {
+      let t = i as f64 / (ticks - 1) as f64;
+      (t, format_legend_value(min + (max - min) * t))
+    })
+    .collect()
+}
+
+/// Formats a value for a legend, dropping the fraction of whole numbers
+fn format_legend_value(value: f64) -> String {
+  if value.fract() == 0.0 {
+    format!("{:.0}", value)
+  } else {
+    format!("{:.2}", value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn legend_panel_widens_the_image_and_keeps_it() {
+    let imgbuf = image::ImageBuffer::from_fn(120, 80, |x, y| image::Rgba([x as u8, y as u8, 100, 255]));
+    let legend = Legend::new()
+      .title("Costs")
+      .colorbar(Some("Cost"), &Viridis, 0.0, 255.0)
+      .swatch("Wall", image::Rgba([17, 17, 17, 255]))
+      .object("Spawn", RenderableObject::Structure(BuildableStructure::Spawn));
+    let output = RenderContext::with_scale_factor(2).append_legend_panel(&imgbuf, &legend);
+
+    assert!(output.width() > imgbuf.width());
+    // The colorbar alone is taller than this image, so the panel grows it
+    assert!(output.height() > imgbuf.height());
+    for (x, y, pixel) in imgbuf.enumerate_pixels() {
+      assert_eq!(output.get_pixel(x, y), pixel, "pixel ({}, {}) changed", x, y);
+    }
+  }
+}
@@ -0,0 +1,286 @@
+use screeps::constants::{Part, ResourceType, structure::StructureType};
+use screeps::enums::StructureObject;
+use screeps::objects::{ConstructionSite, Creep, Mineral, PowerCreep, Ruin, Tombstone};
+use screeps::StructureProperties;
+use screeps_utils::offline_map::OfflineObject;
+
+use super::*;
+
+/// Represents the various types of deposits that can exist in highway rooms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deposit {
+  Biomass,
+  Metal,
+  Mist,
+  Silicon,
+}
+
+/// Represents any in-game room object that can be drawn on a room image
+///
+/// [Resource] and [BuildableStructure] are kept as the per-category types;
+/// this type wraps them and adds the objects that fit in neither.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderableObject {
+  Resource(Resource),
+  Structure(BuildableStructure),
+  ConstructionSite(BuildableStructure),
+  Deposit(Deposit),
+  Ruin,
+  Tombstone,
+  /// A creep with its body part types, drawn in the owner's default color
+  Creep(Vec<Part>),
+}
+
+impl From<Resource> for RenderableObject {
+    #[inline]
+    fn from(resource: Resource) -> RenderableObject {
+      RenderableObject::Resource(resource)
+    }
+}
+
+impl From<BuildableStructure> for RenderableObject {
+    #[inline]
+    fn from(structure: BuildableStructure) -> RenderableObject {
+      RenderableObject::Structure(structure)
+    }
+}
+
+impl From<Deposit> for RenderableObject {
+    #[inline]
+    fn from(deposit: Deposit) -> RenderableObject {
+      RenderableObject::Deposit(deposit)
+    }
+}
+
+impl TryFrom<ResourceType> for Deposit {
+    type Error = ConversionError;
+
+    #[inline]
+    fn try_from(resource_type: ResourceType) -> Result<Deposit, Self::Error> {
+        match resource_type {
+            ResourceType::Biomass => Ok(Deposit::Biomass),
+            ResourceType::Metal   => Ok(Deposit::Metal),
+            ResourceType::Mist    => Ok(Deposit::Mist),
+            ResourceType::Silicon => Ok(Deposit::Silicon),
+            _                     => Err(ConversionError::NotADeposit),
+        }
+    }
+}
+
+impl TryFrom<StructureType> for RenderableObject {
+    type Error = ConversionError;
+
+    #[inline]
+    fn try_from(structure_type: StructureType) -> Result<RenderableObject, Self::Error> {
+        BuildableStructure::try_from(structure_type).map(Into::into)
+    }
+}
+
+impl TryFrom<ResourceType> for RenderableObject {
+    type Error = ConversionError;
+
+    #[inline]
+    fn try_from(resource_type: ResourceType) -> Result<RenderableObject, Self::Error> {
+        Resource::try_from(resource_type).map(Into::into)
+    }
+}
+
+impl TryFrom<&OfflineObject> for RenderableObject {
+    type Error = ConversionError;
+
+    #[inline]
+    fn try_from(obj: &OfflineObject) -> Result<RenderableObject, Self::Error> {
+        match obj {
+            OfflineObject::Source { .. } | OfflineObject::Mineral { .. } => Resource::try_from(obj).map(Into::into),
+            _ => BuildableStructure::try_from(obj).map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<OfflineObject> for RenderableObject {
+    type Error = ConversionError;
+
+    #[inline]
+    fn try_from(obj: OfflineObject) -> Result<RenderableObject, Self::Error> {
+        (&obj).try_into()
+    }
+}
+
+impl From<&Source> for RenderableObject {
+    #[inline]
+    fn from(_source: &Source) -> RenderableObject {
+      RenderableObject::Resource(Resource::Source)
+    }
+}
+
+impl TryFrom<&Mineral> for RenderableObject {
+    type Error = ConversionError;
+
+    #[inline]
+    fn try_from(mineral: &Mineral) -> Result<RenderableObject, Self::Error> {
+        mineral.mineral_type().try_into()
+    }
+}
+
+impl TryFrom<&screeps::objects::Deposit> for RenderableObject {
+    type Error = ConversionError;
+
+    #[inline]
+    fn try_from(deposit: &screeps::objects::Deposit) -> Result<RenderableObject, Self::Error> {
+        Deposit::try_from(deposit.deposit_type()).map(Into::into)
+    }
+}
+
+impl TryFrom<&StructureObject> for RenderableObject {
+    type Error = ConversionError;
+
+    #[inline]
+    fn try_from(structure: &StructureObject) -> Result<RenderableObject, Self::Error> {
+        structure.structure_type().try_into()
+    }
+}
+
+impl TryFrom<&ConstructionSite> for RenderableObject {
+    type Error = ConversionError;
+
+    #[inline]
+    fn try_from(site: &ConstructionSite) -> Result<RenderableObject, Self::Error> {
+        BuildableStructure::try_from(site.structure_type()).map(RenderableObject::ConstructionSite)
+    }
+}
+
+impl From<&Ruin> for RenderableObject {
+    #[inline]
+    fn from(_ruin: &Ruin) -> RenderableObject {
+      RenderableObject::Ruin
+    }
+}
+
+impl From<&Tombstone> for RenderableObject {
+    #[inline]
+    fn from(_tombstone: &Tombstone) -> RenderableObject {
+      RenderableObject::Tombstone
+    }
+}
+
+impl From<&Creep> for RenderableObject {
+    #[inline]
+    fn from(creep: &Creep) -> RenderableObject {
+      RenderableObject::Creep(creep.body().iter().map(|part| part.part()).collect())
+    }
+}
+
+impl From<&PowerCreep> for RenderableObject {
+    #[inline]
+    fn from(_creep: &PowerCreep) -> RenderableObject {
+      RenderableObject::Creep(Vec::new())
+    }
+}
+
+/// Draws a [RenderableObject] at a specific cell location
+pub fn draw_object_xy(imgbuf: &mut OutputImage, col: u32, row: u32, object: &RenderableObject) {
+  draw_object_xy_with_scale_factor(imgbuf, col, row, object, DEFAULT_SCALE_FACTOR)
+}
+
+/// Draws a [RenderableObject] at a specific cell location with a user-supplied scaling factor
+pub fn draw_object_xy_with_scale_factor(imgbuf: &mut OutputImage, col: u32, row: u32, object: &RenderableObject, scale_factor: u32) {
+  RenderContext::with_scale_factor(scale_factor).draw_object_xy(imgbuf, col, row, object)
+}
+
+/// Draws a [Deposit] tile at a specific cell location
+pub fn draw_deposit_tile_xy(imgbuf: &mut OutputImage, col: u32, row: u32, tile: &Deposit) {
+  draw_deposit_tile_xy_with_scale_factor(imgbuf, col, row, tile, DEFAULT_SCALE_FACTOR)
+}
+
+/// Draws a [Deposit] tile at a specific cell location with a user-supplied scaling factor
+pub fn draw_deposit_tile_xy_with_scale_factor(imgbuf: &mut OutputImage, col: u32, row: u32, tile: &Deposit, scale_factor: u32) {
+  RenderContext::with_scale_factor(scale_factor).draw_deposit_tile_xy(imgbuf, col, row, tile)
+}
+
+impl RenderContext {
+  /// Draws a [RenderableObject] at a specific cell location
+  pub fn draw_object_xy(&self, imgbuf: &mut OutputImage, col: u32, row: u32, object: &RenderableObject) {
+    match object {
+      RenderableObject::Resource(resource) => self.draw_resource_tile_xy(imgbuf, col, row, resource),
+      RenderableObject::Structure(structure) => self.draw_buildablestructure_tile_xy(imgbuf, col, row, structure),
+      RenderableObject::ConstructionSite(structure) => {
+        // Construction sites are drawn as a faded version of the structure they will become
+        let mut tile_img = buildablestructure_tile_img(structure).clone();
+        for pixel in tile_img.pixels_mut() {
+          let image::Rgba([r, g, b, a]) = *pixel;
+          *pixel = image::Rgba([r, g, b, a / 3]);
+        }
+        self.draw_tile_img_xy(imgbuf, col, row, &tile_img);
+      },
+      RenderableObject::Deposit(deposit) => self.draw_deposit_tile_xy(imgbuf, col, row, deposit),
+      RenderableObject::Ruin => self.draw_tile_img_xy(imgbuf, col, row, &assets_data::RUIN_IMG),
+      RenderableObject::Tombstone => self.draw_tile_img_xy(imgbuf, col, row, &assets_data::TOMBSTONE_IMG),
+      RenderableObject::Creep(body) => self.draw_creep_xy(imgbuf, col, row, body, DEFAULT_CREEP_OWNER_COLOR),
+    }
+  }
+
+  /// Draws a [Deposit] tile at a specific cell location
+  pub fn draw_deposit_tile_xy(&self, imgbuf: &mut OutputImage, col: u32, row: u32, tile: &Deposit) {
+    let tile_img = match tile {
+      Deposit::Biomass => &*assets_data::DEPOSIT_BIOMASS_IMG,
+      Deposit::Metal   => &*assets_data::DEPOSIT_METAL_IMG,
+      Deposit::Mist    => &*assets_data::DEPOSIT_MIST_IMG,
+      Deposit::Silicon => &*assets_data::DEPOSIT_SILICON_IMG,
+    };
+
+    self.draw_tile_img_xy(imgbuf, col, row, tile_img);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn converts_offline_objects() {
+    let source = offline_object("source", r#","energy":3000,"energyCapacity":3000,"ticksToRegeneration":300"#);
+    assert_eq!(RenderableObject::try_from(&source), Ok(RenderableObject::Resource(Resource::Source)));
+    let mineral = offline_object("mineral", r#","density":3,"mineralType":"H","mineralAmount":70000"#);
+    assert_eq!(RenderableObject::try_from(&mineral), Ok(RenderableObject::Resource(Resource::Hydrogen)));
+    let lair = offline_object("keeperLair", "");
+    assert_eq!(RenderableObject::try_from(lair), Ok(RenderableObject::Structure(BuildableStructure::KeeperLair)));
+    let unknown = offline_object("invaderCore", "");
+    assert_eq!(RenderableObject::try_from(&unknown), Err(ConversionError::UnknownObject));
+  }
+
+  #[test]
+  fn converts_game_types() {
+    assert_eq!(RenderableObject::try_from(StructureType::Tower), Ok(RenderableObject::Structure(BuildableStructure::Tower)));
+    assert_eq!(RenderableObject::try_from(ResourceType::Catalyst), Ok(RenderableObject::Resource(Resource::Catalyst)));
+    assert_eq!(RenderableObject::try_from(ResourceType::Ops), Ok(RenderableObject::Resource(Resource::Other(ResourceType::Ops))));
+  }
+
+  #[test]
+  fn converts_new_structure_and_deposit_types() {
+    assert_eq!(BuildableStructure::try_from(StructureType::InvaderCore), Ok(BuildableStructure::InvaderCore));
+    assert_eq!(BuildableStructure::try_from(StructureType::PowerBank), Ok(BuildableStructure::PowerBank));
+    assert_eq!(RenderableObject::try_from(StructureType::PowerBank), Ok(RenderableObject::Structure(BuildableStructure::PowerBank)));
+    assert_eq!(Deposit::try_from(ResourceType::Mist), Ok(Deposit::Mist));
+    assert_eq!(Deposit::try_from(ResourceType::Energy), Err(ConversionError::NotADeposit));
+  }
+
+  #[test]
+  fn draws_new_sprites() {
+    let objects = [
+      RenderableObject::Structure(BuildableStructure::InvaderCore),
+      RenderableObject::Structure(BuildableStructure::KeeperLair),
+      RenderableObject::Structure(BuildableStructure::Portal),
+      RenderableObject::Structure(BuildableStructure::PowerBank),
+      RenderableObject::Deposit(Deposit::Biomass),
+      RenderableObject::Ruin,
+      RenderableObject::Tombstone,
+    ];
+    let ctx = RenderContext::with_scale_factor(10);
+    let blank = ctx.create_image();
+    for object in objects {
+      let mut imgbuf = blank.clone();
+      ctx.draw_object_xy(&mut imgbuf, 5, 5, &object);
+      assert_ne!(imgbuf, blank, "{:?} wasn't drawn", object);
+    }
+  }
+}
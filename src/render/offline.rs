@@ -0,0 +1,243 @@
+use screeps::local::{RoomCoordinate, RoomName, RoomXY};
+use screeps_utils::offline_map::{OfflineObject, OfflineRoomData, OfflineShardData};
+
+use super::*;
+
+/// Options that control how [render_offline_room] draws a room
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+  /// The number of pixels allocated for each room cell
+  pub scale_factor: u32,
+  /// Whether to draw the cell grid over the finished room
+  pub draw_grid: bool,
+  /// Whether to draw terrain with smooth contours instead of tile sprites; see [RenderContext::draw_room_terrain]
+  pub smooth_terrain: bool,
+  /// The colors used for the background and grid lines
+  pub theme: Theme,
+}
+
+impl Default for RenderOptions {
+  fn default() -> Self {
+    Self {
+      scale_factor: DEFAULT_SCALE_FACTOR,
+      draw_grid: true,
+      smooth_terrain: false,
+      theme: Theme::default(),
+    }
+  }
+}
+
+/// Renders an entire room from an [OfflineRoomData] entry of an offline map dump
+///
+/// Terrain is drawn first, followed by sources and minerals, then structures
+/// (extractors sit on top of their minerals), and finally the grid if requested.
+pub fn render_offline_room(room: &OfflineRoomData, options: &RenderOptions) -> OutputImage {
+  let ctx = RenderContext {
+    theme: options.theme,
+    ..RenderContext::with_scale_factor(options.scale_factor)
+  };
+  let mut imgbuf = ctx.create_image();
+  if options.smooth_terrain {
+    ctx.draw_room_terrain(&mut imgbuf, &room.terrain, &TerrainStyle::default());
+    ctx.draw_offline_objects(&mut imgbuf, room);
+  } else {
+    ctx.draw_offline_room(&mut imgbuf, room);
+  }
+
+  if options.draw_grid {
+    ctx.draw_grid(&mut imgbuf);
+  }
+
+  imgbuf
+}
+
+/// Options that control how [render_world_region_with_options] draws a region of the world
+#[derive(Debug, Clone)]
+pub struct WorldRenderOptions {
+  /// The number of pixels allocated for each room cell
+  pub scale_factor: u32,
+  /// Whether to draw the cell grid over the finished region
+  pub draw_grid: bool,
+  /// Whether to draw lines along the edges of each room
+  pub draw_room_borders: bool,
+  /// Whether to label each room with its name
+  pub draw_room_labels: bool,
+  /// The colors used for the background, grid lines, room borders and labels
+  pub theme: Theme,
+}
+
+impl Default for WorldRenderOptions {
+  fn default() -> Self {
+    Self {
+      scale_factor: DEFAULT_SCALE_FACTOR,
+      draw_grid: false,
+      draw_room_borders: true,
+      draw_room_labels: true,
+      theme: Theme::default(),
+    }
+  }
+}
+
+/// Renders every room between two corner rooms of an offline map dump into a single image
+///
+/// Rooms missing from the dump are left as background. See [WorldCanvas] for the layout.
+pub fn render_world_region(shard: &OfflineShardData, top_left: RoomName, bottom_right: RoomName, scale_factor: u32) -> OutputImage {
+  let options = WorldRenderOptions {
+    scale_factor,
+    ..Default::default()
+  };
+  render_world_region_with_options(shard, top_left, bottom_right, &options)
+}
+
+/// Renders every room between two corner rooms of an offline map dump into a single image, with user-supplied options
+pub fn render_world_region_with_options(shard: &OfflineShardData, top_left: RoomName, bottom_right: RoomName, options: &WorldRenderOptions) -> OutputImage {
+  let ctx = RenderContext {
+    theme: options.theme,
+    ..RenderContext::with_scale_factor(options.scale_factor)
+  };
+  let mut canvas = WorldCanvas::new(ctx, top_left, bottom_right);
+
+  for room_name in canvas.rooms() {
+    let Some(room) = shard.rooms.get(&room_name) else { continue };
+    let mut imgbuf = ctx.create_image();
+    ctx.draw_offline_room(&mut imgbuf, room);
+    canvas.draw_room(room_name, &imgbuf).expect("room should be in the canvas region");
+  }
+
+  if options.draw_grid {
+    canvas.draw_grid();
+  }
+
+  if options.draw_room_borders {
+    canvas.draw_room_borders();
+  }
+
+  if options.draw_room_labels {
+    canvas.draw_room_labels();
+  }
+
+  canvas.into_image()
+}
+
+impl RenderContext {
+  /// Draws an entire room from an [OfflineRoomData] entry of an offline map dump, without a grid
+  ///
+  /// Terrain is drawn first, followed by sources and minerals, then structures
+  /// (extractors sit on top of their minerals).
+  pub fn draw_offline_room(&self, imgbuf: &mut OutputImage, room: &OfflineRoomData) {
+    for col in 0..DEFAULT_ROOM_MAX_COLUMNS {
+      for row in 0..DEFAULT_ROOM_MAX_ROWS {
+        let xy = RoomXY::checked_new(col as u8, row as u8).expect("room cell should be in bounds");
+        let tile = room.terrain.get_xy(xy);
+        self.draw_terrain_tile_xy(imgbuf, col, row, &tile);
+      }
+    }
+
+    self.draw_offline_objects(imgbuf, room);
+  }
+
+  /// Draws the sources, minerals and structures of an [OfflineRoomData] entry, without its terrain
+  fn draw_offline_objects(&self, imgbuf: &mut OutputImage, room: &OfflineRoomData) {
+    let mut objects: Vec<&OfflineObject> = room.objects.iter()
+      .filter(|obj| offline_object_xy(obj).is_some())
+      .collect();
+    objects.sort_by_key(|obj| offline_object_layer(obj));
+
+    for obj in objects {
+      let Some((x, y)) = offline_object_xy(obj) else { continue };
+      let col = x.u8().into();
+      let row = y.u8().into();
+
+      if let Ok(object) = RenderableObject::try_from(obj) {
+        self.draw_object_xy(imgbuf, col, row, &object);
+      }
+    }
+  }
+}
+
+/// Returns the room coordinates of an [OfflineObject], if it has any
+fn offline_object_xy(obj: &OfflineObject) -> Option<(RoomCoordinate, RoomCoordinate)> {
+  match obj {
+    OfflineObject::ConstructedWall { x, y, .. } => Some((*x, *y)),
+    OfflineObject::Controller { x, y, .. } => Some((*x, *y)),
+    OfflineObject::Extractor { x, y, .. } => Some((*x, *y)),
+    OfflineObject::KeeperLair { x, y, .. } => Some((*x, *y)),
+    OfflineObject::Mineral { x, y, .. } => Some((*x, *y)),
+    OfflineObject::Portal { x, y, .. } => Some((*x, *y)),
+    OfflineObject::Source { x, y, .. } => Some((*x, *y)),
+    OfflineObject::Terminal { x, y, .. } => Some((*x, *y)),
+    OfflineObject::Unknown => None,
+  }
+}
+
+/// Returns the drawing layer of an [OfflineObject]; lower layers are drawn first
+fn offline_object_layer(obj: &OfflineObject) -> u8 {
+  match obj {
+    OfflineObject::Source { .. } | OfflineObject::Mineral { .. } => 0,
+    OfflineObject::Portal { .. } => 1,
+    OfflineObject::ConstructedWall { .. } => 2,
+    OfflineObject::Controller { .. }
+      | OfflineObject::KeeperLair { .. }
+      | OfflineObject::Terminal { .. } => 3,
+    OfflineObject::Extractor { .. } => 4,
+    OfflineObject::Unknown => 5,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Builds an offline map dump entry for a room with swamps under (10, 10) and (12, 10)
+  fn room_json(room_name: &str, objects: &str) -> String {
+    let mut terrain = vec!['0'; 2500];
+    for x in [10, 12] {
+      terrain[10 * 50 + x] = '2';
+    }
+    let terrain: String = terrain.into_iter().collect();
+    format!(r#"{{"room":"{}","status":"normal","terrain":"{}","objects":[{}]}}"#, room_name, terrain, objects)
+  }
+
+  #[test]
+  fn draws_objects_over_their_terrain() {
+    let mineral = r#","density":3,"mineralType":"H","mineralAmount":70000"#;
+    let objects = [
+      offline_object_json("keeperLair", 10, 10, ""),
+      offline_object_json("extractor", 12, 10, ""),
+      offline_object_json("mineral", 12, 10, mineral),
+    ];
+    let room: OfflineRoomData = serde_json::from_str(&room_json("W1N1", &objects.join(","))).unwrap();
+    let options = RenderOptions { scale_factor: 10, draw_grid: false, ..Default::default() };
+    let imgbuf = render_offline_room(&room, &options);
+
+    let ctx = RenderContext::with_scale_factor(10);
+    let mut expected = ctx.create_image();
+    for col in 0..50 {
+      for row in 0..50 {
+        let tile = room.terrain.get_xy(RoomXY::checked_new(col as u8, row as u8).unwrap());
+        ctx.draw_terrain_tile_xy(&mut expected, col, row, &tile);
+      }
+    }
+    let swamp_only = expected.clone();
+    ctx.draw_buildablestructure_tile_xy(&mut expected, 10, 10, &BuildableStructure::KeeperLair);
+    ctx.draw_resource_tile_xy(&mut expected, 12, 10, &Resource::Hydrogen);
+    ctx.draw_buildablestructure_tile_xy(&mut expected, 12, 10, &BuildableStructure::Extractor);
+    assert!(imgbuf == expected);
+    assert!(imgbuf != swamp_only);
+  }
+
+  #[test]
+  fn sizes_regions_by_their_rooms() {
+    let shard_json = format!(r#"{{"description":"test","rooms":[{}]}}"#, room_json("W1N1", ""));
+    let shard: OfflineShardData = serde_json::from_str(&shard_json).unwrap();
+    let (top_left, bottom_right) = ("W1N1".parse().unwrap(), "W0N0".parse().unwrap());
+    let imgbuf = render_world_region(&shard, top_left, bottom_right, 2);
+    // Neighboring rooms share the grid line along their edge
+    let border_width = RenderContext::with_scale_factor(2).border_width;
+    assert_eq!(imgbuf.dimensions(), (2 * 50 * 2 + border_width, 2 * 50 * 2 + border_width));
+
+    // Corners may be given in any order
+    let flipped = render_world_region(&shard, bottom_right, top_left, 2);
+    assert!(flipped == imgbuf);
+  }
+}
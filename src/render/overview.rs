@@ -0,0 +1,251 @@
+use screeps::game::map::RoomStatus;
+use screeps::local::RoomName;
+use screeps_utils::offline_map::{OfflineObject, OfflineRoomData, OfflineShardData};
+
+use super::*;
+
+/// The kind of a room, based on where it sits within its sector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectorRoomType {
+  /// A room on a highway between sectors
+  Highway,
+  /// The room in the middle of a sector
+  Center,
+  /// One of the rooms around the sector center, guarded by source keepers
+  SourceKeeper,
+  /// Any other room, which can be claimed
+  Normal,
+}
+
+/// Returns the kind of a room from its position within its sector
+pub fn sector_room_type(room_name: RoomName) -> SectorRoomType {
+  // Wxx and Nyy coordinates are stored as -xx - 1, so flip them back to the number in the name
+  let sector_offset = |coord: i32| if coord < 0 { (-coord - 1) % 10 } else { coord % 10 };
+  let x = sector_offset(room_name.x_coord());
+  let y = sector_offset(room_name.y_coord());
+
+  if (x == 0) | (y == 0) {
+    SectorRoomType::Highway
+  } else if (x == 5) & (y == 5) {
+    SectorRoomType::Center
+  } else if (4..=6).contains(&x) & (4..=6).contains(&y) {
+    SectorRoomType::SourceKeeper
+  } else {
+    SectorRoomType::Normal
+  }
+}
+
+/// The colors used by [render_shard_overview]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverviewPalette {
+  /// Plain tiles in normal rooms
+  pub plain: image::Rgba<u8>,
+  /// Plain tiles in highway rooms
+  pub highway: image::Rgba<u8>,
+  /// Plain tiles in sector center rooms
+  pub center: image::Rgba<u8>,
+  /// Plain tiles in source keeper rooms
+  pub source_keeper: image::Rgba<u8>,
+  /// Swamp tiles, blended over the room's plain color
+  pub swamp: image::Rgba<u8>,
+  /// Wall tiles
+  pub wall: image::Rgba<u8>,
+  /// Every tile of a closed room
+  pub closed: image::Rgba<u8>,
+  /// Portal markers
+  pub portal: image::Rgba<u8>,
+  /// The border around novice zones
+  pub novice_border: image::Rgba<u8>,
+  /// The border around respawn zones
+  pub respawn_border: image::Rgba<u8>,
+}
+
+impl Default for OverviewPalette {
+  fn default() -> Self {
+    Self {
+      plain: image::Rgba([52, 52, 52, 255]),
+      highway: image::Rgba([74, 74, 92, 255]),
+      center: image::Rgba([98, 84, 52, 255]),
+      source_keeper: image::Rgba([88, 50, 50, 255]),
+      swamp: image::Rgba([40, 70, 20, 160]),
+      wall: image::Rgba([14, 14, 14, 255]),
+      closed: image::Rgba([24, 24, 24, 255]),
+      portal: image::Rgba([150, 110, 255, 255]),
+      novice_border: image::Rgba([90, 210, 90, 255]),
+      respawn_border: image::Rgba([80, 160, 255, 255]),
+    }
+  }
+}
+
+/// Options that control how [render_shard_overview_with_options] draws a shard
+#[derive(Debug, Clone)]
+pub struct OverviewOptions {
+  /// The number of pixels for each room cell; 1 to 3 keeps whole shards to a manageable size
+  pub scale_factor: u32,
+  /// Whether to tint highway, center and source keeper rooms
+  pub highlight_sector_rooms: bool,
+  /// Whether to mark portals
+  pub draw_portals: bool,
+  /// Whether to outline novice and respawn zones
+  pub draw_zone_borders: bool,
+  /// The colors to draw with
+  pub palette: OverviewPalette,
+}
+
+impl Default for OverviewOptions {
+  fn default() -> Self {
+    Self {
+      scale_factor: 1,
+      highlight_sector_rooms: true,
+      draw_portals: true,
+      draw_zone_borders: true,
+      palette: OverviewPalette::default(),
+    }
+  }
+}
+
+/// Renders every room of an offline map dump into a single low-resolution overview image
+pub fn render_shard_overview(shard: &OfflineShardData) -> OutputImage {
+  render_shard_overview_with_options(shard, &OverviewOptions::default())
+}
+
+/// Renders every room of an offline map dump into a single low-resolution overview image, with user-supplied options
+///
+/// Tiles are filled with flat colors rather than sprites, and the image is sized
+/// to the smallest rectangle of rooms containing the whole dump.
+pub fn render_shard_overview_with_options(shard: &OfflineShardData, options: &OverviewOptions) -> OutputImage {
+  let ctx = RenderContext {
+    scale_factor: options.scale_factor,
+    border_width: 0,
+    ..Default::default()
+  };
+
+  let Some(first) = shard.rooms.keys().next() else {
+    return image::ImageBuffer::new(0, 0);
+  };
+  let min_x = shard.rooms.keys().map(RoomName::x_coord).min().unwrap_or_default();
+  let max_x = shard.rooms.keys().map(RoomName::x_coord).max().unwrap_or_default();
+  let min_y = shard.rooms.keys().map(RoomName::y_coord).min().unwrap_or_default();
+  let max_y = shard.rooms.keys().map(RoomName::y_coord).max().unwrap_or_default();
+  let corner = |x: i32, y: i32| first.checked_add((x - first.x_coord(), y - first.y_coord())).expect("corner should be a valid room");
+
+  let mut canvas = WorldCanvas::new(ctx, corner(min_x, min_y), corner(max_x, max_y));
+  let palette = &options.palette;
+
+  for room in shard.rooms.values() {
+    let Ok((origin_x, origin_y)) = canvas.room_origin(room.room_name) else { continue };
+    let plain = match (room.status, options.highlight_sector_rooms) {
+      (RoomStatus::Closed, _) => palette.closed,
+      (_, false) => palette.plain,
+      (_, true) => match sector_room_type(room.room_name) {
+        SectorRoomType::Highway      => palette.highway,
+        SectorRoomType::Center       => palette.center,
+        SectorRoomType::SourceKeeper => palette.source_keeper,
+        SectorRoomType::Normal       => palette.plain,
+      },
+    };
+
+    let imgbuf = canvas.image_mut();
+    for index in 0..ROOM_AREA {
+      let xy = screeps::local::terrain_index_to_xy(index);
+      let color = match (room.status, room.terrain.get_xy(xy)) {
+        (RoomStatus::Closed, _) => palette.closed,
+        (_, Terrain::Wall)  => palette.wall,
+        (_, Terrain::Swamp) => blend(plain, palette.swamp),
+        (_, Terrain::Plain) => plain,
+      };
+      fill_overview_cell(imgbuf, &ctx, origin_x, origin_y, xy.x.u8().into(), xy.y.u8().into(), color);
+    }
+  }
+
+  if options.draw_zone_borders {
+    for room in shard.rooms.values() {
+      let color = match room.status {
+        RoomStatus::Novice  => palette.novice_border,
+        RoomStatus::Respawn => palette.respawn_border,
+        _ => continue,
+      };
+      draw_zone_border(&mut canvas, shard, room, color);
+    }
+  }
+
+  if options.draw_portals {
+    for room in shard.rooms.values() {
+      let Ok((origin_x, origin_y)) = canvas.room_origin(room.room_name) else { continue };
+      for obj in &room.objects {
+        let OfflineObject::Portal { x, y, .. } = obj else { continue };
+        // Portals are a single tile, so mark them with a larger square to keep them visible
+        for dx in -2..=2_i32 {
+          for dy in -2..=2_i32 {
+            let col = i32::from(x.u8()) + dx;
+            let row = i32::from(y.u8()) + dy;
+            if (0..ROOM_SIZE as i32).contains(&col) & (0..ROOM_SIZE as i32).contains(&row) {
+              fill_overview_cell(canvas.image_mut(), &ctx, origin_x, origin_y, col as u32, row as u32, palette.portal);
+            }
+          }
+        }
+      }
+    }
+  }
+
+  canvas.into_image()
+}
+
+/// Outlines the edges of a room that border a room with a different status, or no room at all
+fn draw_zone_border(canvas: &mut WorldCanvas, shard: &OfflineShardData, room: &OfflineRoomData, color: image::Rgba<u8>) {
+  let ctx = *canvas.context();
+  let Ok((origin_x, origin_y)) = canvas.room_origin(room.room_name) else { return };
+  let differs = |offset: (i32, i32)| {
+    room.room_name.checked_add(offset)
+      .and_then(|neighbor| shard.rooms.get(&neighbor))
+      .map_or(true, |neighbor| neighbor.status != room.status)
+  };
+  let (north, south, west, east) = (differs((0, -1)), differs((0, 1)), differs((-1, 0)), differs((1, 0)));
+  let last = ROOM_SIZE as u32 - 1;
+
+  for col in 0..ROOM_SIZE as u32 {
+    for row in 0..ROOM_SIZE as u32 {
+      if (north & (row == 0)) | (south & (row == last)) | (west & (col == 0)) | (east & (col == last)) {
+        fill_overview_cell(canvas.image_mut(), &ctx, origin_x, origin_y, col, row, color);
+      }
+    }
+  }
+}
+
+/// Fills one cell of a room whose image starts at the given pixel location
+fn fill_overview_cell(imgbuf: &mut OutputImage, ctx: &RenderContext, origin_x: u32, origin_y: u32, col: u32, row: u32, color: image::Rgba<u8>) {
+  let (x, y) = ctx.cell_origin(col, row);
+  for draw_x in (origin_x + x)..(origin_x + x + ctx.scale_factor).min(imgbuf.width()) {
+    for draw_y in (origin_y + y)..(origin_y + y + ctx.scale_factor).min(imgbuf.height()) {
+      imgbuf.put_pixel(draw_x, draw_y, color);
+    }
+  }
+}
+
+/// Blends a translucent color over an opaque one
+fn blend(base: image::Rgba<u8>, over: image::Rgba<u8>) -> image::Rgba<u8> {
+  use image::Pixel;
+
+  let mut blended = base;
+  blended.blend(&over);
+  blended
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn classifies_rooms_by_sector_position() {
+    let cases = [
+      ("W10N3", SectorRoomType::Highway),
+      ("E0S5", SectorRoomType::Highway),
+      ("W5N5", SectorRoomType::Center),
+      ("W4N6", SectorRoomType::SourceKeeper),
+      ("W1N1", SectorRoomType::Normal),
+    ];
+    for (room_name, room_type) in cases {
+      assert_eq!(sector_room_type(room_name.parse().unwrap()), room_type, "{}", room_name);
+    }
+  }
+}
@@ -0,0 +1,279 @@
+use screeps::constants::Direction;
+use screeps::local::{Position, RoomCoordinate, RoomXY};
+
+use super::*;
+use super::visual::{bounds, dash_pattern, CoverageMask};
+
+/// Style options for [RenderContext::draw_path]
+///
+/// Sizes are in room cells. The line, arrowheads and step markers are drawn
+/// as one layer, so they don't darken each other where they overlap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathStyle {
+  pub color: Option<String>,
+  pub width: Option<f32>,
+  pub opacity: Option<f32>,
+  pub line_style: LineDrawStyle,
+  /// Whether to draw an arrowhead at the end of the path
+  pub arrowhead: bool,
+  /// Also draw an arrowhead on every this many steps, to show the direction of long paths
+  pub arrow_spacing: Option<usize>,
+  /// Whether to draw a dot on every step
+  pub step_markers: bool,
+  pub marker_radius: Option<f32>,
+}
+
+impl Default for PathStyle {
+  fn default() -> Self {
+    Self {
+      color: None,
+      width: None,
+      opacity: None,
+      line_style: LineDrawStyle::Solid,
+      arrowhead: true,
+      arrow_spacing: None,
+      step_markers: false,
+      marker_radius: None,
+    }
+  }
+}
+
+impl PathStyle {
+  pub fn color(mut self, val: &str) -> PathStyle {
+    self.color = Some(val.to_string());
+    self
+  }
+
+  pub fn width(mut self, val: f32) -> PathStyle {
+    self.width = Some(val);
+    self
+  }
+
+  pub fn opacity(mut self, val: f32) -> PathStyle {
+    self.opacity = Some(val);
+    self
+  }
+
+  pub fn line_style(mut self, val: LineDrawStyle) -> PathStyle {
+    self.line_style = val;
+    self
+  }
+
+  pub fn arrowhead(mut self, val: bool) -> PathStyle {
+    self.arrowhead = val;
+    self
+  }
+
+  pub fn arrow_spacing(mut self, val: usize) -> PathStyle {
+    self.arrow_spacing = Some(val);
+    self
+  }
+
+  pub fn step_markers(mut self, val: bool) -> PathStyle {
+    self.step_markers = val;
+    self
+  }
+
+  pub fn marker_radius(mut self, val: f32) -> PathStyle {
+    self.marker_radius = Some(val);
+    self
+  }
+}
+
+/// Errors that can occur when parsing a serialized path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathParseError {
+  /// The path doesn't start with a two-digit x and y coordinate of its first step
+  InvalidStart,
+  /// A character after the start isn't a direction from 1 to 8
+  InvalidDirection { index: usize, character: char },
+  /// A direction leads off the edge of the room
+  OutOfRoom { index: usize },
+}
+
+impl std::fmt::Display for PathParseError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PathParseError::InvalidStart => write!(f, "path does not start with the coordinates of its first step"),
+      PathParseError::InvalidDirection { index, character } => write!(f, "invalid direction {:?} at index {}", character, index),
+      PathParseError::OutOfRoom { index } => write!(f, "direction at index {} leads out of the room", index),
+    }
+  }
+}
+
+impl std::error::Error for PathParseError {}
+
+/// Parses a path serialized by `Room.serializePath`, as stored in creep memory
+///
+/// The first four digits are the x and y coordinates of the first step. Each
+/// following digit is the direction moved to reach the next step, except for
+/// the first one, which is the direction moved to reach the first step.
+pub fn parse_serialized_path(path: &str) -> Result<Vec<RoomXY>, PathParseError> {
+  let coord = |digits: Option<&str>| {
+    digits
+      .and_then(|digits| digits.parse::<u8>().ok())
+      .and_then(|value| RoomCoordinate::new(value).ok())
+      .ok_or(PathParseError::InvalidStart)
+  };
+  let mut xy = RoomXY::new(coord(path.get(0..2))?, coord(path.get(2..4))?);
+
+  let mut steps = Vec::new();
+  for (index, character) in path.char_indices().skip(4) {
+    let direction = match character {
+      '1' => Direction::Top,
+      '2' => Direction::TopRight,
+      '3' => Direction::Right,
+      '4' => Direction::BottomRight,
+      '5' => Direction::Bottom,
+      '6' => Direction::BottomLeft,
+      '7' => Direction::Left,
+      '8' => Direction::TopLeft,
+      _ => return Err(PathParseError::InvalidDirection { index, character }),
+    };
+    if index > 4 {
+      xy = xy.checked_add_direction(direction).ok_or(PathParseError::OutOfRoom { index })?;
+    }
+    steps.push(xy);
+  }
+
+  Ok(steps)
+}
+
+/// Draws a path on a default-sized image
+pub fn draw_path(imgbuf: &mut OutputImage, path: &[RoomXY], style: &PathStyle) {
+  draw_path_with_scale_factor(imgbuf, path, style, DEFAULT_SCALE_FACTOR)
+}
+
+/// Draws a path on an image with user-supplied scaling
+pub fn draw_path_with_scale_factor(imgbuf: &mut OutputImage, path: &[RoomXY], style: &PathStyle, scale_factor: u32) {
+  RenderContext::with_scale_factor(scale_factor).draw_path(imgbuf, path, style)
+}
+
+/// Returns the room coordinates of each step, for drawing
+fn xy_points(path: &[RoomXY]) -> Vec<(f32, f32)> {
+  path.iter().map(|xy| (xy.x.u8() as f32, xy.y.u8() as f32)).collect()
+}
+
+/// Returns the unit vector from one point to another, or `None` if they are the same
+fn direction_between(from: (f32, f32), to: (f32, f32)) -> Option<(f32, f32)> {
+  let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+  let length = (dx * dx + dy * dy).sqrt();
+  (length > 0.0).then(|| (dx / length, dy / length))
+}
+
+impl RenderContext {
+  /// Draws a path through a list of steps in this room, like the result of `PathFinder.search`
+  pub fn draw_path(&self, imgbuf: &mut OutputImage, path: &[RoomXY], style: &PathStyle) {
+    self.draw_path_points(imgbuf, &xy_points(path), style)
+  }
+
+  /// Draws a path through the steps of a list of positions, ignoring which room each is in
+  pub fn draw_position_path(&self, imgbuf: &mut OutputImage, path: &[Position], style: &PathStyle) {
+    let steps: Vec<RoomXY> = path.iter().map(|position| position.xy()).collect();
+    self.draw_path(imgbuf, &steps, style)
+  }
+
+  /// Draws a path serialized by `Room.serializePath`; see [parse_serialized_path]
+  pub fn draw_serialized_path(&self, imgbuf: &mut OutputImage, path: &str, style: &PathStyle) -> Result<(), PathParseError> {
+    let steps = parse_serialized_path(path)?;
+    self.draw_path(imgbuf, &steps, style);
+    Ok(())
+  }
+
+  /// Draws a path through a list of room coordinates, which may be fractional or outside the room
+  pub(super) fn draw_path_points(&self, imgbuf: &mut OutputImage, points: &[(f32, f32)], style: &PathStyle) {
+    let Some(&last) = points.last() else { return };
+    let Some(color) = parse_color(style.color.as_deref().unwrap_or("#ffffff")) else { return };
+    let scale_factor = self.scale_factor as f32;
+    let width = style.width.unwrap_or(0.1);
+    let opacity = style.opacity.unwrap_or(0.5);
+    let arrow_length = (width * 4.0).max(0.5);
+    let arrow_half_width = arrow_length * 0.4;
+    let marker_radius = style.marker_radius.unwrap_or(width * 1.5);
+    let to_pixels = |points: &[(f32, f32)]| -> Vec<(f32, f32)> {
+      points.iter().map(|(x, y)| self.room_to_pixel(*x, *y)).collect()
+    };
+
+    // Accumulate every part in one mask over the path's bounds, then blend it in with the path's opacity
+    let margin = arrow_length.max(marker_radius).max(width) * scale_factor + 1.0;
+    let (min, max) = bounds(&to_pixels(points), margin);
+    let Some(mut mask) = CoverageMask::new(imgbuf, min, max) else { return };
+    let draw_arrow = |mask: &mut CoverageMask, tip: (f32, f32), (dx, dy): (f32, f32)| {
+      let base = (tip.0 - dx * arrow_length, tip.1 - dy * arrow_length);
+      let (nx, ny) = (-dy * arrow_half_width, dx * arrow_half_width);
+      let triangle = to_pixels(&[tip, (base.0 + nx, base.1 + ny), (base.0 - nx, base.1 - ny), tip]);
+      mask.fill_polygon(&triangle);
+      mask.stroke_polyline(&triangle, 0.01 * scale_factor, None);
+    };
+
+    let mut line = points.to_vec();
+    let end_direction = points.iter().rev().find_map(|point| direction_between(*point, last));
+    if let (true, Some((dx, dy))) = (style.arrowhead, end_direction) {
+      // Stop the line at the base of the arrowhead so its end doesn't poke through the tip
+      let end = line.len() - 1;
+      line[end] = (last.0 - dx * arrow_length * 0.8, last.1 - dy * arrow_length * 0.8);
+      draw_arrow(&mut mask, last, (dx, dy));
+    }
+    if line.len() > 1 {
+      let line_width = width * scale_factor;
+      mask.stroke_polyline(&to_pixels(&line), line_width / 2.0, dash_pattern(style.line_style, line_width));
+    }
+
+    if let Some(spacing) = style.arrow_spacing.filter(|spacing| *spacing > 0) {
+      // Center the arrows between steps, so they stay clear of the step markers
+      for index in (spacing..points.len()).step_by(spacing) {
+        let (from, to) = (points[index - 1], points[index]);
+        let Some((dx, dy)) = direction_between(from, to) else { continue };
+        let (x, y) = ((from.0 + to.0) / 2.0, (from.1 + to.1) / 2.0);
+        let tip = (x + dx * arrow_length / 2.0, y + dy * arrow_length / 2.0);
+        draw_arrow(&mut mask, tip, (dx, dy));
+      }
+    }
+
+    if style.step_markers {
+      for center in to_pixels(points) {
+        mask.fill_disc(center, marker_radius * scale_factor);
+      }
+    }
+
+    mask.composite(imgbuf, color, opacity);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_serialized_path() {
+    // `Room.serializePath` of steps (10, 20), (11, 20), (12, 19), (12, 18) reached from (9, 20)
+    let steps = parse_serialized_path("10203321").unwrap();
+    assert_eq!(steps, vec![xy(10, 20), xy(11, 20), xy(12, 19), xy(12, 18)]);
+  }
+
+  #[test]
+  fn parses_path_with_only_a_start() {
+    assert_eq!(parse_serialized_path("0549"), Ok(vec![]));
+    assert_eq!(parse_serialized_path("05495"), Ok(vec![xy(5, 49)]));
+  }
+
+  #[test]
+  fn rejects_invalid_directions() {
+    assert_eq!(parse_serialized_path("1020330"), Err(PathParseError::InvalidDirection { index: 6, character: '0' }));
+    assert_eq!(parse_serialized_path("102039"), Err(PathParseError::InvalidDirection { index: 5, character: '9' }));
+    assert_eq!(parse_serialized_path("10203x"), Err(PathParseError::InvalidDirection { index: 5, character: 'x' }));
+  }
+
+  #[test]
+  fn rejects_invalid_start() {
+    assert_eq!(parse_serialized_path(""), Err(PathParseError::InvalidStart));
+    assert_eq!(parse_serialized_path("102"), Err(PathParseError::InvalidStart));
+    assert_eq!(parse_serialized_path("1a203"), Err(PathParseError::InvalidStart));
+    assert_eq!(parse_serialized_path("50203"), Err(PathParseError::InvalidStart));
+  }
+
+  #[test]
+  fn rejects_steps_out_of_the_room() {
+    assert_eq!(parse_serialized_path("004955"), Err(PathParseError::OutOfRoom { index: 5 }));
+  }
+}
@@ -0,0 +1,254 @@
+use screeps::constants::structure::StructureType;
+use screeps::local::{LocalRoomTerrain, RoomXY};
+
+use super::*;
+
+/// The highest room controller level
+const MAX_RCL: u8 = 8;
+
+/// A single structure placement in a [BasePlan]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlannedStructure {
+  pub structure_type: StructureType,
+  pub xy: RoomXY,
+  /// The room controller level from which the structure is built
+  pub min_rcl: u8,
+}
+
+/// A structure type with more placements at some level than `CONTROLLER_STRUCTURES` allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverbuiltStructure {
+  pub structure_type: StructureType,
+  pub planned: u32,
+  pub allowed: u32,
+}
+
+/// A base layout, with each structure annotated with the controller level it is built at
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BasePlan {
+  pub structures: Vec<PlannedStructure>,
+}
+
+impl BasePlan {
+  /// Creates an empty plan
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a structure placement, built from the given controller level
+  pub fn structure(mut self, structure_type: StructureType, xy: RoomXY, min_rcl: u8) -> Self {
+    self.structures.push(PlannedStructure { structure_type, xy, min_rcl });
+    self
+  }
+
+  /// Returns the placements built by a controller level
+  pub fn at_rcl(&self, rcl: u8) -> impl Iterator<Item = &PlannedStructure> {
+    self.structures.iter().filter(move |structure| structure.min_rcl <= rcl)
+  }
+
+  /// Returns the structure types with more placements at a controller level than the game allows
+  ///
+  /// Types that can never be built, such as controllers, aren't checked. Types
+  /// are listed in the order they first appear in the plan.
+  pub fn overbuilt(&self, rcl: u8) -> Vec<OverbuiltStructure> {
+    let mut counts: Vec<(StructureType, u32)> = Vec::new();
+    for structure in self.at_rcl(rcl) {
+      match counts.iter_mut().find(|(structure_type, _)| *structure_type == structure.structure_type) {
+        Some((_, count)) => *count += 1,
+        None => counts.push((structure.structure_type, 1)),
+      }
+    }
+
+    counts.into_iter()
+      .filter(|(structure_type, _)| structure_type.controller_structures(MAX_RCL.into()) > 0)
+      .map(|(structure_type, planned)| OverbuiltStructure {
+        structure_type,
+        planned,
+        allowed: structure_type.controller_structures(rcl.into()),
+      })
+      .filter(|overbuilt| overbuilt.planned > overbuilt.allowed)
+      .collect()
+  }
+}
+
+/// Options that control how [render_base_plan] and [render_base_plan_stages] draw a plan
+#[derive(Debug, Clone)]
+pub struct BasePlanOptions<'a> {
+  /// The number of pixels allocated for each room cell
+  pub scale_factor: u32,
+  /// The room's terrain, drawn under the plan if given
+  pub terrain: Option<&'a LocalRoomTerrain>,
+  /// Whether to draw the cell grid over the finished plan
+  pub draw_grid: bool,
+  /// The number of panels in each row of [render_base_plan_stages]
+  pub panel_columns: u32,
+}
+
+impl Default for BasePlanOptions<'_> {
+  fn default() -> Self {
+    Self {
+      scale_factor: DEFAULT_SCALE_FACTOR,
+      terrain: None,
+      draw_grid: true,
+      panel_columns: 4,
+    }
+  }
+}
+
+/// Renders the structures of a plan built by a controller level
+pub fn render_base_plan(plan: &BasePlan, rcl: u8, options: &BasePlanOptions) -> OutputImage {
+  let ctx = RenderContext::with_scale_factor(options.scale_factor);
+  let mut imgbuf = ctx.create_image();
+
+  if let Some(terrain) = options.terrain {
+    for index in 0..(ROOM_SIZE as usize * ROOM_SIZE as usize) {
+      let xy = screeps::local::terrain_index_to_xy(index);
+      ctx.draw_terrain_tile_xy(&mut imgbuf, xy.x.u8().into(), xy.y.u8().into(), &terrain.get_xy(xy));
+    }
+  }
+
+  ctx.draw_base_plan(&mut imgbuf, plan, rcl, options.terrain);
+
+  if options.draw_grid {
+    ctx.draw_grid(&mut imgbuf);
+  }
+
+  imgbuf
+}
+
+/// Renders a grid of panels showing a plan at each controller level from 1 to 8
+///
+/// Each panel is titled with its level, in the theme's error color if any
+/// structure type is over the limit at that level. The overbuilt types are
+/// listed under the panel, one per line, with their planned and allowed counts.
+pub fn render_base_plan_stages(plan: &BasePlan, options: &BasePlanOptions) -> OutputImage {
+  let ctx = RenderContext::with_scale_factor(options.scale_factor);
+  let font = &assets_data::FREE_MONO_FONT;
+  let columns = options.panel_columns.clamp(1, MAX_RCL.into());
+  let rows = (MAX_RCL as u32).div_ceil(columns);
+  let (panel_width, panel_height) = (ctx.image_width(), ctx.image_height());
+  let title_height = (panel_height / 16).max(12);
+  let text_height = title_height * 4 / 5;
+  let gap = title_height / 2;
+
+  // Every row leaves room under its panels for the longest list of overbuilt types
+  let overbuilt: Vec<Vec<OverbuiltStructure>> = (1..=MAX_RCL).map(|rcl| plan.overbuilt(rcl)).collect();
+  let notes_height = overbuilt.iter().map(Vec::len).max().unwrap_or(0) as u32 * title_height;
+  let row_height = title_height + panel_height + notes_height;
+
+  let width = columns * panel_width + (columns + 1) * gap;
+  let height = rows * row_height + (rows + 1) * gap;
+  let mut output = image::ImageBuffer::from_pixel(width, height, ctx.theme.background);
+  let error_color = image::Rgba([ctx.theme.error[0], ctx.theme.error[1], ctx.theme.error[2], 255]);
+
+  for (rcl, overbuilt) in (1..=MAX_RCL).zip(&overbuilt) {
+    let index = (rcl - 1) as u32;
+    let left = gap + (index % columns) * (panel_width + gap);
+    let top = gap + (index / columns) * (row_height + gap);
+    let mut draw_line = |text: &str, top: u32, color: image::Rgba<u8>| {
+      let (scale, _, _) = calculate_text_scale_within(font, panel_width, text_height, text);
+      imageproc::drawing::draw_text_mut(&mut output, color, left as i32, top as i32, scale, font, text);
+    };
+
+    let color = if overbuilt.is_empty() { ctx.theme.text } else { error_color };
+    draw_line(&format!("RCL {}", rcl), top, color);
+    let notes_top = top + title_height + panel_height;
+    for (line, structure) in (0..).zip(overbuilt) {
+      let note = format!("{} {}/{}", structure.structure_type, structure.planned, structure.allowed);
+      draw_line(&note, notes_top + line * title_height + gap / 2, error_color);
+    }
+
+    let panel = render_base_plan(plan, rcl, options);
+    image::imageops::replace(&mut output, &panel, left.into(), (top + title_height).into());
+  }
+
+  output
+}
+
+impl RenderContext {
+  /// Draws the structures of a plan built by a controller level, flagging types over the limit
+  ///
+  /// Structures are layered as [RenderContext::draw_structure_stacks] does, so
+  /// roads sit under the other structures and ramparts over them. Every
+  /// placement of an overbuilt type is tinted with the theme's error color.
+  /// The terrain, if given, colors roads on swamps and through walls as
+  /// [RoadStyle::color_by_terrain] does. Returns the overbuilt types, as
+  /// [BasePlan::overbuilt] does.
+  pub fn draw_base_plan(&self, imgbuf: &mut OutputImage, plan: &BasePlan, rcl: u8, terrain: Option<&LocalRoomTerrain>) -> Vec<OverbuiltStructure> {
+    let structures: Vec<&PlannedStructure> = plan.at_rcl(rcl).collect();
+    let stacks: Vec<(RoomXY, BuildableStructure)> = structures.iter()
+      .filter_map(|structure| BuildableStructure::try_from(structure.structure_type).ok().map(|tile| (structure.xy, tile)))
+      .collect();
+    let style = StructureStackStyle {
+      road: RoadStyle { color_by_terrain: true, ..Default::default() },
+      ..Default::default()
+    };
+    self.draw_structure_stacks(imgbuf, &stacks, terrain, &style);
+
+    let overbuilt = plan.overbuilt(rcl);
+    let flagged: Vec<(u8, u8)> = structures.iter()
+      .filter(|structure| overbuilt.iter().any(|overbuilt| overbuilt.structure_type == structure.structure_type))
+      .map(|structure| (structure.xy.x.u8(), structure.xy.y.u8()))
+      .collect();
+    let overlay = self.get_tile_alpha_overlay_multi_tile(imgbuf.width(), imgbuf.height(), self.theme.error, &flagged);
+    image::imageops::overlay(imgbuf, &overlay, 0, 0);
+
+    overbuilt
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn plan_of(structure_type: StructureType, count: u8, min_rcl: u8) -> BasePlan {
+    (0..count).fold(BasePlan::new(), |plan, x| plan.structure(structure_type, xy(x, 10), min_rcl))
+  }
+
+  #[test]
+  fn overbuilt_at_extension_limits() {
+    let exact = plan_of(StructureType::Extension, 5, 2);
+    assert!(exact.overbuilt(1).is_empty());
+    assert!(exact.overbuilt(2).is_empty());
+
+    let over = plan_of(StructureType::Extension, 6, 2);
+    let expected = OverbuiltStructure { structure_type: StructureType::Extension, planned: 6, allowed: 5 };
+    assert!(over.overbuilt(1).is_empty());
+    assert_eq!(over.overbuilt(2), vec![expected]);
+    assert!(over.overbuilt(3).is_empty());
+  }
+
+  #[test]
+  fn overbuilt_counts_structures_built_at_lower_levels() {
+    let plan = plan_of(StructureType::Extension, 5, 2).structure(StructureType::Extension, xy(0, 20), 3);
+    assert!(plan.overbuilt(2).is_empty());
+    assert!(plan.overbuilt(3).is_empty());
+
+    let plan = plan_of(StructureType::Extension, 6, 1);
+    let expected = OverbuiltStructure { structure_type: StructureType::Extension, planned: 6, allowed: 0 };
+    assert_eq!(plan.overbuilt(1), vec![expected]);
+  }
+
+  #[test]
+  fn overbuilt_at_spawn_limits() {
+    let plan = plan_of(StructureType::Spawn, 3, 1);
+    assert_eq!(plan.overbuilt(6)[0].allowed, 1);
+    assert_eq!(plan.overbuilt(7)[0].allowed, 2);
+    assert!(plan.overbuilt(8).is_empty());
+  }
+
+  #[test]
+  fn overbuilt_lists_types_in_plan_order() {
+    let plan = plan_of(StructureType::Tower, 2, 3).structure(StructureType::Observer, xy(0, 30), 1);
+    let types: Vec<StructureType> = plan.overbuilt(4).iter().map(|overbuilt| overbuilt.structure_type).collect();
+    assert_eq!(types, vec![StructureType::Tower, StructureType::Observer]);
+    assert_eq!(plan.overbuilt(5), vec![OverbuiltStructure { structure_type: StructureType::Observer, planned: 1, allowed: 0 }]);
+    assert!(plan.overbuilt(8).is_empty());
+  }
+
+  #[test]
+  fn overbuilt_ignores_unbuildable_types() {
+    let plan = plan_of(StructureType::Controller, 2, 1);
+    assert!(plan.overbuilt(8).is_empty());
+  }
+}
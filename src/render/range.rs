@@ -0,0 +1,204 @@
+use screeps::constants::{TOWER_FALLOFF, TOWER_FALLOFF_RANGE, TOWER_OPTIMAL_RANGE};
+use screeps::local::RoomXY;
+
+use super::*;
+
+/// How the strength of an effect changes with range from its source
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RangeFalloff {
+  /// Full strength at every range
+  Constant,
+  /// Full strength up to a range, then falling linearly to a minimum strength at the source's maximum range
+  Linear { full_strength_range: u8, min_strength: f64 },
+  /// The effectiveness of tower attacks, heals and repairs; see [tower_effectiveness]
+  Tower,
+}
+
+impl RangeFalloff {
+  /// Returns the strength, between 0.0 and 1.0, at a distance from a source with a given maximum range
+  pub fn strength(&self, distance: u8, range: u8) -> f64 {
+    match *self {
+      RangeFalloff::Constant => 1.0,
+      RangeFalloff::Linear { full_strength_range, min_strength } => {
+        if (distance <= full_strength_range) | (range <= full_strength_range) {
+          1.0
+        } else {
+          let t = (distance - full_strength_range) as f64 / (range - full_strength_range) as f64;
+          1.0 - (1.0 - min_strength) * t.min(1.0)
+        }
+      },
+      RangeFalloff::Tower => tower_effectiveness(distance),
+    }
+  }
+}
+
+/// Returns the fraction of a tower's full power that it applies at a range
+///
+/// Matches the game's falloff: full power up to [TOWER_OPTIMAL_RANGE], falling
+/// linearly to a quarter at [TOWER_FALLOFF_RANGE] and beyond.
+pub fn tower_effectiveness(range: u8) -> f64 {
+  tower_amount_at_range(1.0, range)
+}
+
+/// Applies tower falloff to an amount in the same order as the game, so whole tower powers stay whole
+fn tower_amount_at_range(amount: f64, range: u8) -> f64 {
+  let range = range.clamp(TOWER_OPTIMAL_RANGE, TOWER_FALLOFF_RANGE);
+  amount - amount * TOWER_FALLOFF * (range - TOWER_OPTIMAL_RANGE) as f64 / (TOWER_FALLOFF_RANGE - TOWER_OPTIMAL_RANGE) as f64
+}
+
+/// Returns every tile within a Chebyshev range of a tile, as used by `inRangeTo`, clipped to the room
+pub fn tiles_in_range(center: RoomXY, range: u8) -> Vec<(u8, u8)> {
+  let (x, y) = (center.x.u8(), center.y.u8());
+  let last = ROOM_SIZE - 1;
+  let xs = x.saturating_sub(range)..=x.saturating_add(range).min(last);
+  let ys = y.saturating_sub(range)..=y.saturating_add(range).min(last);
+  ys.flat_map(|row| xs.clone().map(move |col| (col, row))).collect()
+}
+
+/// A source of an area effect, such as a tower or a creep's ranged attack
+///
+/// Sources can be drawn with [RenderContext::draw_range_sources], or with
+/// [RenderContext::draw_heatmap] directly; a list of sources adds together
+/// wherever their ranges overlap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangeSource {
+  pub center: RoomXY,
+  pub range: u8,
+  /// The value at full strength
+  pub weight: f64,
+  pub falloff: RangeFalloff,
+}
+
+impl RangeSource {
+  /// Creates a full-strength source with a weight of 1.0
+  pub fn new(center: RoomXY, range: u8) -> Self {
+    Self { center, range, weight: 1.0, falloff: RangeFalloff::Constant }
+  }
+
+  /// Creates a source covering the whole room with tower falloff and a weight of 1.0
+  pub fn tower(center: RoomXY) -> Self {
+    Self { center, range: ROOM_SIZE, weight: 1.0, falloff: RangeFalloff::Tower }
+  }
+
+  pub fn weight(mut self, val: f64) -> Self {
+    self.weight = val;
+    self
+  }
+
+  pub fn falloff(mut self, val: RangeFalloff) -> Self {
+    self.falloff = val;
+    self
+  }
+}
+
+impl HeatmapSource for RangeSource {
+  fn value(&self, xy: RoomXY) -> Option<f64> {
+    let distance = self.center.get_range_to(xy);
+    (distance <= self.range.into()).then(|| match self.falloff {
+      RangeFalloff::Tower => tower_amount_at_range(self.weight, distance as u8),
+      falloff => self.weight * falloff.strength(distance as u8, self.range),
+    })
+  }
+}
+
+/// Overlapping sources add up; tiles out of range of every source have no value
+impl HeatmapSource for &[RangeSource] {
+  fn value(&self, xy: RoomXY) -> Option<f64> {
+    self.iter()
+      .filter_map(|source| source.value(xy))
+      .fold(None, |total, value| Some(total.unwrap_or(0.0) + value))
+  }
+}
+
+impl HeatmapSource for Vec<RangeSource> {
+  fn value(&self, xy: RoomXY) -> Option<f64> {
+    self.as_slice().value(xy)
+  }
+}
+
+/// Shades every tile within range of a tile on a default-sized image
+pub fn draw_range(imgbuf: &mut OutputImage, center: RoomXY, range: u8, rgba: image::Rgba<u8>) {
+  draw_range_with_scale_factor(imgbuf, center, range, rgba, DEFAULT_SCALE_FACTOR)
+}
+
+/// Shades every tile within range of a tile on an image with user-supplied scaling
+pub fn draw_range_with_scale_factor(imgbuf: &mut OutputImage, center: RoomXY, range: u8, rgba: image::Rgba<u8>, scale_factor: u32) {
+  RenderContext::with_scale_factor(scale_factor).draw_range(imgbuf, center, range, rgba)
+}
+
+/// Draws the combined strength of area effect sources on a default-sized image
+pub fn draw_range_sources(imgbuf: &mut OutputImage, sources: &[RangeSource], colormap: &dyn Colormap, options: &HeatmapOptions) -> Option<(f64, f64)> {
+  draw_range_sources_with_scale_factor(imgbuf, sources, colormap, options, DEFAULT_SCALE_FACTOR)
+}
+
+/// Draws the combined strength of area effect sources on an image with user-supplied scaling
+pub fn draw_range_sources_with_scale_factor(imgbuf: &mut OutputImage, sources: &[RangeSource], colormap: &dyn Colormap, options: &HeatmapOptions, scale_factor: u32) -> Option<(f64, f64)> {
+  RenderContext::with_scale_factor(scale_factor).draw_range_sources(imgbuf, sources, colormap, options)
+}
+
+impl RenderContext {
+  /// Creates a transparent overlay sized for the given image, with every tile within range of a tile filled in
+  pub fn get_range_overlay(&self, overlay_width: u32, overlay_height: u32, rgba: image::Rgba<u8>, center: RoomXY, range: u8) -> OutputImage {
+    self.get_tile_alpha_overlay_multi_tile(overlay_width, overlay_height, rgba, &tiles_in_range(center, range))
+  }
+
+  /// Shades every tile within range of a tile with a translucent color
+  pub fn draw_range(&self, imgbuf: &mut OutputImage, center: RoomXY, range: u8, rgba: image::Rgba<u8>) {
+    self.draw_ranges(imgbuf, &[(center, range)], rgba)
+  }
+
+  /// Shades every tile within range of any of several tiles
+  ///
+  /// Each range is blended in separately, so tiles covered by more of them are shaded more heavily.
+  pub fn draw_ranges(&self, imgbuf: &mut OutputImage, ranges: &[(RoomXY, u8)], rgba: image::Rgba<u8>) {
+    for (center, range) in ranges {
+      let overlay = self.get_range_overlay(imgbuf.width(), imgbuf.height(), rgba, *center, *range);
+      image::imageops::overlay(imgbuf, &overlay, 0, 0);
+    }
+  }
+
+  /// Draws the combined strength of area effect sources as a heatmap, adding up overlapping sources
+  ///
+  /// Tiles out of range of every source are left uncolored. Returns the value range
+  /// that was mapped onto the colormap, as [RenderContext::draw_heatmap] does.
+  pub fn draw_range_sources(&self, imgbuf: &mut OutputImage, sources: &[RangeSource], colormap: &dyn Colormap, options: &HeatmapOptions) -> Option<(f64, f64)> {
+    self.draw_heatmap(imgbuf, &sources, colormap, options)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn tiles_in_range_clips_to_the_room() {
+    let corner = tiles_in_range(xy(0, 0), 2);
+    assert_eq!(corner.len(), 9);
+    assert!(corner.iter().all(|(x, y)| (*x <= 2) & (*y <= 2)));
+
+    let edge = tiles_in_range(xy(49, 25), 1);
+    assert_eq!(edge, vec![(48, 24), (49, 24), (48, 25), (49, 25), (48, 26), (49, 26)]);
+
+    assert_eq!(tiles_in_range(xy(25, 25), 60).len(), 2500);
+    assert_eq!(tiles_in_range(xy(10, 10), 0), vec![(10, 10)]);
+  }
+
+  #[test]
+  fn linear_falloff_at_its_boundaries() {
+    let falloff = RangeFalloff::Linear { full_strength_range: 2, min_strength: 0.25 };
+    assert_eq!(falloff.strength(0, 6), 1.0);
+    assert_eq!(falloff.strength(2, 6), 1.0);
+    assert_eq!(falloff.strength(4, 6), 0.625);
+    assert_eq!(falloff.strength(6, 6), 0.25);
+    // A range no longer than the full strength range has no falloff
+    assert_eq!(falloff.strength(2, 2), 1.0);
+  }
+
+  #[test]
+  fn tower_source_matches_effectiveness() {
+    let source = RangeSource::tower(xy(25, 25));
+    assert_eq!(source.value(xy(25, 30)), Some(1.0));
+    assert_eq!(source.value(xy(25, 45)), Some(0.25));
+    assert_eq!(source.value(xy(25, 15)), Some(tower_effectiveness(10)));
+  }
+}
@@ -0,0 +1,119 @@
+use super::*;
+
+/// An error encountered while parsing serialized room visuals
+#[derive(Debug)]
+pub struct VisualParseError {
+  /// The 1-based line of the input that failed to parse
+  pub line: usize,
+  /// The underlying JSON error
+  pub source: serde_json::Error,
+}
+
+impl std::fmt::Display for VisualParseError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "invalid visual on line {}: {}", self.line, self.source)
+  }
+}
+
+impl std::error::Error for VisualParseError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    Some(&self.source)
+  }
+}
+
+/// Parses serialized room visuals, as returned by `RoomVisual.export()` or stored by the server
+///
+/// The input is newline-delimited JSON with one [Visual] per line; blank lines are skipped.
+pub fn parse_visuals(data: &str) -> Result<Vec<Visual>, VisualParseError> {
+  data.lines()
+    .enumerate()
+    .filter(|(_, line)| !line.trim().is_empty())
+    .map(|(index, line)| serde_json::from_str(line).map_err(|source| VisualParseError { line: index + 1, source }))
+    .collect()
+}
+
+/// Replays serialized room visuals onto a new room image
+pub fn render_visuals(data: &str) -> Result<OutputImage, VisualParseError> {
+  render_visuals_with_scale_factor(data, DEFAULT_SCALE_FACTOR)
+}
+
+/// Replays serialized room visuals onto a new room image with a user-supplied scaling factor
+pub fn render_visuals_with_scale_factor(data: &str, scale_factor: u32) -> Result<OutputImage, VisualParseError> {
+  let ctx = RenderContext::with_scale_factor(scale_factor);
+  let mut imgbuf = ctx.create_image();
+  ctx.replay_visuals(&mut imgbuf, data)?;
+  Ok(imgbuf)
+}
+
+impl RenderContext {
+  /// Draws a list of [Visual]s in order
+  pub fn draw_visuals(&self, imgbuf: &mut OutputImage, visuals: &[Visual]) {
+    for visual in visuals {
+      self.draw_visual(imgbuf, visual);
+    }
+  }
+
+  /// Parses serialized room visuals and draws them in order; see [parse_visuals]
+  ///
+  /// Nothing is drawn if any line fails to parse.
+  pub fn replay_visuals(&self, imgbuf: &mut OutputImage, data: &str) -> Result<(), VisualParseError> {
+    let visuals = parse_visuals(data)?;
+    self.draw_visuals(imgbuf, &visuals);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_one_visual_per_line() {
+    let data = r##"{"t":"c","x":10,"y":10,"s":{"radius":3,"fill":"#ff0000"}}
+{"t":"l","x1":1,"y1":2,"x2":3,"y2":4}
+{"t":"r","x":5,"y":6,"w":2,"h":1}
+{"t":"p","points":[[1,1],[2,2],[3,1]]}
+{"t":"t","text":"hello","x":25,"y":25}"##;
+    let visuals = parse_visuals(data).unwrap();
+
+    assert_eq!(visuals.len(), 5);
+    assert!(matches!(&visuals[0], Visual::Circle { x, y, style: Some(_) } if (*x, *y) == (10.0, 10.0)));
+    assert!(matches!(&visuals[1], Visual::Line { x1, y2, style: None, .. } if (*x1, *y2) == (1.0, 4.0)));
+    assert!(matches!(&visuals[2], Visual::Rect { width, height, .. } if (*width, *height) == (2.0, 1.0)));
+    assert!(matches!(&visuals[3], Visual::Poly { points, .. } if points.len() == 3));
+    assert!(matches!(&visuals[4], Visual::Text { text, .. } if text == "hello"));
+  }
+
+  #[test]
+  fn skips_blank_lines() {
+    let data = "\n{\"t\":\"c\",\"x\":1,\"y\":1}\n   \n{\"t\":\"c\",\"x\":2,\"y\":2}\n";
+    assert_eq!(parse_visuals(data).unwrap().len(), 2);
+    assert!(parse_visuals("").unwrap().is_empty());
+  }
+
+  #[test]
+  fn reports_the_line_of_a_malformed_visual() {
+    let data = "{\"t\":\"c\",\"x\":1,\"y\":1}\n\n{\"t\":\"c\",\"x\":1\n{\"t\":\"c\",\"x\":2,\"y\":2}";
+    let error = parse_visuals(data).unwrap_err();
+    assert_eq!(error.line, 3);
+    assert!(error.to_string().starts_with("invalid visual on line 3:"));
+  }
+
+  #[test]
+  fn rejects_unknown_visual_types_and_missing_fields() {
+    assert_eq!(parse_visuals("{\"t\":\"z\",\"x\":1,\"y\":1}").unwrap_err().line, 1);
+    assert_eq!(parse_visuals("{\"t\":\"c\",\"x\":1,\"y\":1}\n{\"t\":\"l\",\"x1\":1}").unwrap_err().line, 2);
+    assert_eq!(parse_visuals("not json").unwrap_err().line, 1);
+  }
+
+  #[test]
+  fn draws_nothing_when_any_line_fails() {
+    let ctx = RenderContext::with_scale_factor(4);
+    let mut imgbuf = ctx.create_image();
+    let blank = imgbuf.clone();
+    let data = "{\"t\":\"c\",\"x\":10,\"y\":10,\"s\":{\"radius\":3,\"fill\":\"#ff0000\"}}\nbad";
+
+    assert!(ctx.replay_visuals(&mut imgbuf, data).is_err());
+    assert_eq!(imgbuf, blank);
+  }
+}
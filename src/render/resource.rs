@@ -0,0 +1,191 @@
+use screeps::constants::ResourceType;
+
+use super::*;
+
+/// Icons are drawn at this multiple of the final size and then scaled down, to smooth their edges
+const ICON_SUPERSAMPLING: u32 = 4;
+
+/// Background color used behind resource labels
+const ICON_BACKGROUND_COLOR: image::Rgba<u8> = image::Rgba([34, 34, 34, 255]);
+
+/// Returns the color the official client uses for a resource type
+///
+/// Compounds take the color of the mineral they are based on, and
+/// commodities take the color of their production chain.
+pub fn resource_color(resource_type: ResourceType) -> image::Rgba<u8> {
+  use ResourceType::*;
+
+  let [r, g, b] = match resource_type {
+    Energy | Battery => [255, 229, 109],
+    Power => [244, 31, 51],
+    Ops => [250, 137, 56],
+
+    Hydrogen | Oxygen | Hydroxide | ZynthiumKeanite | UtriumLemergite | Oxidant | Reductant => [180, 180, 180],
+    Catalyst | Purifier => [255, 123, 123],
+    Ghodium | GhodiumHydride | GhodiumOxide | GhodiumAcid | GhodiumAlkalide
+      | CatalyzedGhodiumAcid | CatalyzedGhodiumAlkalide | GhodiumMelt => [255, 255, 255],
+    Utrium | UtriumHydride | UtriumOxide | UtriumAcid | UtriumAlkalide
+      | CatalyzedUtriumAcid | CatalyzedUtriumAlkalide | UtriumBar => [80, 215, 249],
+    Keanium | KeaniumHydride | KeaniumOxide | KeaniumAcid | KeaniumAlkalide
+      | CatalyzedKeaniumAcid | CatalyzedKeaniumAlkalide | KeaniumBar => [160, 113, 255],
+    Lemergium | LemergiumHydride | LemergiumOxide | LemergiumAcid | LemergiumAlkalide
+      | CatalyzedLemergiumAcid | CatalyzedLemergiumAlkalide | LemergiumBar => [0, 244, 162],
+    Zynthium | ZynthiumHydride | ZynthiumOxide | ZynthiumAcid | ZynthiumAlkalide
+      | CatalyzedZynthiumAcid | CatalyzedZynthiumAlkalide | ZynthiumBar => [253, 211, 136],
+
+    Composite | Crystal | Liquid => [204, 204, 204],
+    Metal | Alloy | Tube | Fixtures | Frame | Hydraulics | Machine => [149, 111, 92],
+    Biomass | Cell | Phlegm | Tissue | Muscle | Organoid | Organism => [132, 176, 18],
+    Silicon | Wire | Switch | Transistor | Microchip | Circuit | Device => [76, 167, 229],
+    Mist | Condensate | Concentrate | Extract | Spirit | Emanation | Essence => [218, 107, 245],
+
+    _ => [119, 119, 119],
+  };
+
+  image::Rgba([r, g, b, 255])
+}
+
+/// Draws a procedurally generated icon for any [ResourceType] at a specific cell location
+pub fn draw_resource_type_icon_xy(imgbuf: &mut OutputImage, col: u32, row: u32, resource_type: ResourceType) {
+  draw_resource_type_icon_xy_with_scale_factor(imgbuf, col, row, resource_type, DEFAULT_SCALE_FACTOR)
+}
+
+/// Draws a procedurally generated icon for any [ResourceType] at a specific cell location with a user-supplied scaling factor
+pub fn draw_resource_type_icon_xy_with_scale_factor(imgbuf: &mut OutputImage, col: u32, row: u32, resource_type: ResourceType, scale_factor: u32) {
+  RenderContext::with_scale_factor(scale_factor).draw_resource_type_icon_xy(imgbuf, col, row, resource_type)
+}
+
+impl RenderContext {
+  /// Draws a procedurally generated icon for any [ResourceType] at a specific cell location
+  ///
+  /// Energy and power are drawn as plain colored dots, minerals as a ring with their
+  /// symbol, and compounds and commodities as a rounded label with their name.
+  pub fn draw_resource_type_icon_xy(&self, imgbuf: &mut OutputImage, col: u32, row: u32, resource_type: ResourceType) {
+    let scale_factor = self.scale_factor;
+    let size = scale_factor * ICON_SUPERSAMPLING;
+    let mut icon: OutputImage = image::ImageBuffer::new(size, size);
+    let color = resource_color(resource_type);
+    let center = (size / 2) as i32;
+    let label = resource_type.to_string();
+
+    match icon_shape(resource_type) {
+      IconShape::Dot => {
+        imageproc::drawing::draw_filled_circle_mut(&mut icon, (center, center), (size * 3 / 10) as i32, color);
+      },
+      IconShape::Ring => {
+        let radius = (size * 2 / 5) as i32;
+        imageproc::drawing::draw_filled_circle_mut(&mut icon, (center, center), radius, color);
+        imageproc::drawing::draw_filled_circle_mut(&mut icon, (center, center), radius * 17 / 20, ICON_BACKGROUND_COLOR);
+        let text_area = (radius as u32) * 6 / 5;
+        draw_icon_label(&mut icon, &label, color, text_area, text_area);
+      },
+      IconShape::Label => {
+        let width = size * 9 / 10;
+        let height = size / 2;
+        let border = (height / 10).max(1);
+        draw_rounded_rect(&mut icon, width, height, color);
+        draw_rounded_rect(&mut icon, width.saturating_sub(2 * border), height.saturating_sub(2 * border), ICON_BACKGROUND_COLOR);
+        draw_icon_label(&mut icon, &label, color, width - 2 * height / 5, height * 3 / 5);
+      },
+    }
+
+    let icon = image::imageops::resize(&icon, scale_factor, scale_factor, image::imageops::FilterType::Triangle);
+    self.draw_tile_img_xy(imgbuf, col, row, &icon);
+  }
+}
+
+/// The kinds of procedural icon drawn for resources
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IconShape {
+  /// A plain colored dot, for energy and power
+  Dot,
+  /// A ring around the symbol, for base minerals
+  Ring,
+  /// A rounded label with the name, for compounds and commodities
+  Label,
+}
+
+/// Picks the icon drawn for a resource type
+fn icon_shape(resource_type: ResourceType) -> IconShape {
+  use ResourceType::*;
+
+  match resource_type {
+    Energy | Power => IconShape::Dot,
+    Hydrogen | Oxygen | Utrium | Lemergium | Keanium | Zynthium | Catalyst => IconShape::Ring,
+    _ => IconShape::Label,
+  }
+}
+
+/// Draws a rounded rectangle of the given size, centered in the icon
+///
+/// Nothing is drawn for an empty size, which tiny icons can shrink to.
+fn draw_rounded_rect(icon: &mut OutputImage, width: u32, height: u32, color: image::Rgba<u8>) {
+  if (width == 0) | (height == 0) {
+    return;
+  }
+  let radius = height.min(width) / 2;
+  let left = (icon.width() - width) / 2;
+  let top = (icon.height() - height) / 2;
+  let center_y = (top + radius) as i32;
+
+  imageproc::drawing::draw_filled_circle_mut(icon, ((left + radius) as i32, center_y), radius as i32, color);
+  imageproc::drawing::draw_filled_circle_mut(icon, ((left + width - radius) as i32, center_y), radius as i32, color);
+  if width > 2 * radius {
+    let rect = imageproc::rect::Rect::at((left + radius) as i32, top as i32).of_size(width - 2 * radius, height);
+    imageproc::drawing::draw_filled_rect_mut(icon, rect, color);
+  }
+}
+
+/// Draws text centered in the icon, shrunk to fit the given area
+fn draw_icon_label(icon: &mut OutputImage, text: &str, color: image::Rgba<u8>, width: u32, height: u32) {
+  let font = &assets_data::FREE_MONO_FONT;
+  let (scale, text_width, text_height) = calculate_text_scale_within(font, width, height, text);
+  let x = (icon.width().saturating_sub(text_width) / 2) as i32;
+  let y = (icon.height().saturating_sub(text_height) / 2) as i32;
+  imageproc::drawing::draw_text_mut(icon, color, x, y, scale, font, text);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn base_minerals_are_drawn_as_rings() {
+    for mineral in [ResourceType::Hydrogen, ResourceType::Oxygen, ResourceType::Utrium, ResourceType::Lemergium, ResourceType::Keanium, ResourceType::Zynthium, ResourceType::Catalyst] {
+      assert_eq!(icon_shape(mineral), IconShape::Ring, "{:?}", mineral);
+    }
+  }
+
+  #[test]
+  fn two_letter_compounds_are_not_drawn_as_minerals() {
+    assert_eq!(icon_shape(ResourceType::UtriumHydride), IconShape::Label);
+    for compound in [ResourceType::Hydroxide, ResourceType::ZynthiumKeanite, ResourceType::UtriumLemergite, ResourceType::GhodiumOxide] {
+      assert_eq!(icon_shape(compound), IconShape::Label, "{:?}", compound);
+    }
+  }
+
+  #[test]
+  fn energy_and_power_are_drawn_as_dots() {
+    assert_eq!(icon_shape(ResourceType::Energy), IconShape::Dot);
+    assert_eq!(icon_shape(ResourceType::Power), IconShape::Dot);
+  }
+
+  #[test]
+  fn every_icon_shape_draws_at_small_scales() {
+    let resources = [
+      (ResourceType::Energy, IconShape::Dot),
+      (ResourceType::Hydrogen, IconShape::Ring),
+      (ResourceType::UtriumHydride, IconShape::Label),
+      (ResourceType::Ops, IconShape::Label),
+    ];
+    for scale_factor in 1..=3 {
+      for (resource_type, shape) in resources {
+        assert_eq!(icon_shape(resource_type), shape);
+        let mut imgbuf = RenderContext::with_scale_factor(scale_factor).create_image();
+        draw_resource_type_icon_xy_with_scale_factor(&mut imgbuf, 1, 1, resource_type, scale_factor);
+      }
+      let mut imgbuf = RenderContext::with_scale_factor(scale_factor).create_image();
+      draw_resource_tile_xy_with_scale_factor(&mut imgbuf, 1, 1, &Resource::Other(ResourceType::Ops), scale_factor);
+    }
+  }
+}
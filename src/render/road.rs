@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+
+use screeps::constants::Direction;
+use screeps::local::{LocalRoomTerrain, RoomXY};
+
+use super::*;
+use super::visual::CoverageMask;
+
+/// The directions to each of a tile's eight neighbors
+const NEIGHBOR_DIRECTIONS: [Direction; 8] = [
+  Direction::Top, Direction::TopRight, Direction::Right, Direction::BottomRight,
+  Direction::Bottom, Direction::BottomLeft, Direction::Left, Direction::TopLeft,
+];
+
+/// Style options for [RenderContext::draw_roads]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoadStyle {
+  /// The width of road segments, in room cells
+  pub width: f32,
+  /// Roads on plain tiles, and every road when coloring by terrain is off
+  pub plain: image::Rgba<u8>,
+  /// Roads on swamp tiles
+  pub swamp: image::Rgba<u8>,
+  /// Roads tunnelled through wall tiles
+  pub tunnel: image::Rgba<u8>,
+  /// Whether to color each tile's part of the network by the terrain under it
+  pub color_by_terrain: bool,
+}
+
+impl Default for RoadStyle {
+  fn default() -> Self {
+    Self {
+      width: 0.35,
+      plain: image::Rgba([110, 110, 110, 255]),
+      swamp: image::Rgba([84, 104, 56, 255]),
+      tunnel: image::Rgba([150, 118, 84, 255]),
+      color_by_terrain: false,
+    }
+  }
+}
+
+/// Draws a road network on a default-sized image
+pub fn draw_roads(imgbuf: &mut OutputImage, roads: &[RoomXY], terrain: Option<&LocalRoomTerrain>, style: &RoadStyle) {
+  draw_roads_with_scale_factor(imgbuf, roads, terrain, style, DEFAULT_SCALE_FACTOR)
+}
+
+/// Draws a road network on an image with user-supplied scaling
+pub fn draw_roads_with_scale_factor(imgbuf: &mut OutputImage, roads: &[RoomXY], terrain: Option<&LocalRoomTerrain>, style: &RoadStyle, scale_factor: u32) {
+  RenderContext::with_scale_factor(scale_factor).draw_roads(imgbuf, roads, terrain, style)
+}
+
+impl RenderContext {
+  /// Draws a road network, joining each road tile to its neighboring road tiles like the game client
+  ///
+  /// Each road is a dot with a segment reaching halfway to every adjacent road,
+  /// including diagonally. If a terrain is given and the style colors by
+  /// terrain, each tile's dot and half-segments take the color of its terrain.
+  pub fn draw_roads(&self, imgbuf: &mut OutputImage, roads: &[RoomXY], terrain: Option<&LocalRoomTerrain>, style: &RoadStyle) {
+    let scale_factor = self.scale_factor as f32;
+    let road_set: HashSet<RoomXY> = roads.iter().copied().collect();
+    let mut drawn = HashSet::new();
+
+    for road in roads.iter().filter(|road| drawn.insert(**road)) {
+      let color = match (style.color_by_terrain, terrain.map(|terrain| terrain.get_xy(*road))) {
+        (true, Some(Terrain::Swamp)) => style.swamp,
+        (true, Some(Terrain::Wall))  => style.tunnel,
+        _ => style.plain,
+      };
+      let (x, y) = (road.x.u8() as f32, road.y.u8() as f32);
+      let center = self.room_to_pixel(x, y);
+      let half_width = style.width * scale_factor / 2.0;
+      let extent = scale_factor / 2.0 + half_width + 1.0;
+      let Some(mut mask) = CoverageMask::new(imgbuf, (center.0 - extent, center.1 - extent), (center.0 + extent, center.1 + extent)) else { continue };
+
+      mask.fill_disc(center, half_width);
+      for direction in NEIGHBOR_DIRECTIONS {
+        let Some(neighbor) = road.checked_add_direction(direction) else { continue };
+        if road_set.contains(&neighbor) {
+          let halfway = self.room_to_pixel((x + neighbor.x.u8() as f32) / 2.0, (y + neighbor.y.u8() as f32) / 2.0);
+          mask.stroke_polyline(&[center, halfway], half_width, None);
+        }
+      }
+      mask.composite(imgbuf, color, 1.0);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Returns the pixel at a fractional room coordinate after drawing roads on a blank image
+  fn pixel_between(roads: &[RoomXY], x: f32, y: f32) -> image::Rgba<u8> {
+    let ctx = RenderContext::with_scale_factor(20);
+    let mut imgbuf = ctx.create_image();
+    ctx.draw_roads(&mut imgbuf, roads, None, &RoadStyle::default());
+    let (px, py) = ctx.room_to_pixel(x, y);
+    *imgbuf.get_pixel(px as u32, py as u32)
+  }
+
+  #[test]
+  fn joins_diagonal_roads() {
+    let plain = RoadStyle::default().plain;
+    assert_eq!(pixel_between(&[xy(10, 10), xy(11, 11)], 10.5, 10.5), plain);
+    assert_eq!(pixel_between(&[xy(11, 10), xy(10, 11)], 10.5, 10.5), plain);
+  }
+
+  #[test]
+  fn leaves_gaps_between_roads_that_arent_adjacent() {
+    let plain = RoadStyle::default().plain;
+    assert_ne!(pixel_between(&[xy(10, 10), xy(12, 12)], 11.0, 11.0), plain);
+    assert_ne!(pixel_between(&[xy(10, 10), xy(11, 10)], 10.5, 10.5), plain);
+    assert_eq!(pixel_between(&[xy(10, 10), xy(11, 10)], 10.5, 10.0), plain);
+  }
+}
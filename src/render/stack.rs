@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+
+use screeps::local::{LocalRoomTerrain, RoomXY};
+
+use super::*;
+
+/// Style options for [RenderContext::draw_structure_stacks]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StructureStackStyle {
+  /// The style of the road network under everything else
+  pub road: RoadStyle,
+  /// The translucent color filling rampart tiles
+  pub rampart_fill: image::Rgba<u8>,
+  /// The color of the outline around each group of adjacent ramparts
+  pub rampart_outline: image::Rgba<u8>,
+  /// The width of the rampart outline, in room cells
+  pub rampart_outline_width: f32,
+}
+
+impl Default for StructureStackStyle {
+  fn default() -> Self {
+    Self {
+      road: RoadStyle::default(),
+      rampart_fill: image::Rgba([67, 134, 59, 80]),
+      rampart_outline: image::Rgba([112, 196, 98, 230]),
+      rampart_outline_width: 0.12,
+    }
+  }
+}
+
+/// Draws layered structures on a default-sized image
+pub fn draw_structure_stacks(imgbuf: &mut OutputImage, structures: &[(RoomXY, BuildableStructure)], terrain: Option<&LocalRoomTerrain>, style: &StructureStackStyle) {
+  draw_structure_stacks_with_scale_factor(imgbuf, structures, terrain, style, DEFAULT_SCALE_FACTOR)
+}
+
+/// Draws layered structures on an image with user-supplied scaling
+pub fn draw_structure_stacks_with_scale_factor(imgbuf: &mut OutputImage, structures: &[(RoomXY, BuildableStructure)], terrain: Option<&LocalRoomTerrain>, style: &StructureStackStyle, scale_factor: u32) {
+  RenderContext::with_scale_factor(scale_factor).draw_structure_stacks(imgbuf, structures, terrain, style)
+}
+
+impl RenderContext {
+  /// Draws structures in layers, so several can share a tile as in the game
+  ///
+  /// Roads are drawn first as a connected network (see [RenderContext::draw_roads]),
+  /// then every other structure's sprite, and finally ramparts as a translucent
+  /// cover with one outline around each group of adjacent ramparts. The terrain
+  /// is only used to color roads.
+  pub fn draw_structure_stacks(&self, imgbuf: &mut OutputImage, structures: &[(RoomXY, BuildableStructure)], terrain: Option<&LocalRoomTerrain>, style: &StructureStackStyle) {
+    let of_type = |wanted: BuildableStructure| -> Vec<RoomXY> {
+      structures.iter()
+        .filter(|(_, structure)| *structure == wanted)
+        .map(|(xy, _)| *xy)
+        .collect()
+    };
+
+    self.draw_roads(imgbuf, &of_type(BuildableStructure::Road), terrain, &style.road);
+
+    for (xy, structure) in structures {
+      if !matches!(structure, BuildableStructure::Road | BuildableStructure::Rampart) {
+        self.draw_buildablestructure_tile_xy(imgbuf, xy.x.u8().into(), xy.y.u8().into(), structure);
+      }
+    }
+
+    self.draw_ramparts(imgbuf, &of_type(BuildableStructure::Rampart), style);
+  }
+
+  /// Draws ramparts as a translucent cover, outlining only the edges that don't touch another rampart
+  ///
+  /// Adjacent ramparts merge into one shape, so a rampart wall reads as a single contiguous outline.
+  pub fn draw_ramparts(&self, imgbuf: &mut OutputImage, ramparts: &[RoomXY], style: &StructureStackStyle) {
+    use image::Pixel;
+
+    let rampart_set: HashSet<(i32, i32)> = ramparts.iter()
+      .map(|xy| (xy.x.u8() as i32, xy.y.u8() as i32))
+      .collect();
+    let is_rampart = |x: i32, y: i32| rampart_set.contains(&(x, y));
+    let pixels = self.scale_factor as f32;
+    let width = style.rampart_outline_width;
+
+    for &(x, y) in &rampart_set {
+      let (left, top) = self.cell_origin(x as u32, y as u32);
+      for py in top..(top + self.scale_factor).min(imgbuf.height()) {
+        for px in left..(left + self.scale_factor).min(imgbuf.width()) {
+          let u = (px - left) as f32 / pixels + 0.5 / pixels;
+          let v = (py - top) as f32 / pixels + 0.5 / pixels;
+
+          // The distance to the nearest edge or corner point that borders open ground
+          let mut distance = f32::INFINITY;
+          for (dx, dy, edge_distance) in [(-1, 0, u), (1, 0, 1.0 - u), (0, -1, v), (0, 1, 1.0 - v)] {
+            if !is_rampart(x + dx, y + dy) {
+              distance = distance.min(edge_distance);
+            }
+          }
+          for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            if !is_rampart(x + dx, y + dy) {
+              let corner = ((dx + 1) / 2, (dy + 1) / 2);
+              distance = distance.min(((u - corner.0 as f32).powi(2) + (v - corner.1 as f32).powi(2)).sqrt());
+            }
+          }
+
+          let coverage = ((width - distance) * pixels + 0.5).clamp(0.0, 1.0);
+          let [r, g, b, a] = style.rampart_outline.0;
+          let outline = image::Rgba([r, g, b, (a as f32 * coverage).round() as u8]);
+          let pixel = imgbuf.get_pixel_mut(px, py);
+          pixel.blend(&style.rampart_fill);
+          pixel.blend(&outline);
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use image::Pixel;
+
+  #[test]
+  fn adjacent_ramparts_share_one_outline() {
+    let ctx = RenderContext::with_scale_factor(20);
+    let style = StructureStackStyle::default();
+    let blank = ctx.create_image();
+    let mut imgbuf = blank.clone();
+    ctx.draw_ramparts(&mut imgbuf, &[xy(10, 10), xy(11, 10)], &style);
+
+    let (left, top) = ctx.cell_origin(10, 10);
+    let fill_only = |px: u32, py: u32| {
+      let mut pixel = *blank.get_pixel(px, py);
+      pixel.blend(&style.rampart_fill);
+      pixel
+    };
+
+    // The shared edge between the two ramparts is only filled, on both sides of it
+    let shared_left = (left + ctx.scale_factor - 1, top + ctx.scale_factor / 2);
+    let shared_right = (left + ctx.scale_factor, top + ctx.scale_factor / 2);
+    for (px, py) in [shared_left, shared_right] {
+      assert_eq!(*imgbuf.get_pixel(px, py), fill_only(px, py), "({}, {})", px, py);
+    }
+
+    // The open edges around the pair keep their outline
+    let open_edges = [
+      (left, top + ctx.scale_factor / 2),
+      (left + 2 * ctx.scale_factor - 1, top + ctx.scale_factor / 2),
+      (left + ctx.scale_factor / 2, top),
+      (left + ctx.scale_factor + ctx.scale_factor / 2, top + ctx.scale_factor - 1),
+    ];
+    for (px, py) in open_edges {
+      assert_ne!(*imgbuf.get_pixel(px, py), fill_only(px, py), "({}, {})", px, py);
+    }
+  }
+
+  #[test]
+  fn structures_show_through_ramparts() {
+    let ctx = RenderContext::with_scale_factor(20);
+    let style = StructureStackStyle::default();
+    let draw = |structures: &[(RoomXY, BuildableStructure)]| {
+      let mut imgbuf = ctx.create_image();
+      ctx.draw_structure_stacks(&mut imgbuf, structures, None, &style);
+      imgbuf
+    };
+
+    let spawn = draw(&[(xy(10, 10), BuildableStructure::Spawn)]);
+    let rampart = draw(&[(xy(10, 10), BuildableStructure::Rampart)]);
+    let both = draw(&[(xy(10, 10), BuildableStructure::Rampart), (xy(10, 10), BuildableStructure::Spawn)]);
+
+    let (left, top) = ctx.cell_origin(10, 10);
+    let center = (left + ctx.scale_factor / 2, top + ctx.scale_factor / 2);
+
+    // The spawn is drawn under the rampart regardless of their order, and tinted by its fill
+    let mut tinted_spawn = *spawn.get_pixel(center.0, center.1);
+    tinted_spawn.blend(&style.rampart_fill);
+    assert_eq!(*both.get_pixel(center.0, center.1), tinted_spawn);
+    assert_ne!(*both.get_pixel(center.0, center.1), *rampart.get_pixel(center.0, center.1));
+  }
+}
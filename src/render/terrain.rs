@@ -0,0 +1,158 @@
+use screeps::local::{LocalRoomTerrain, RoomXY};
+
+use super::*;
+
+/// Style options for [RenderContext::draw_room_terrain]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerrainStyle {
+  pub plain: image::Rgba<u8>,
+  pub swamp: image::Rgba<u8>,
+  pub wall: image::Rgba<u8>,
+  /// Walls along the edge of the room, which can never be exits
+  pub natural_wall: image::Rgba<u8>,
+  /// A tint over passable tiles along the edge of the room, which lead to the neighboring room
+  ///
+  /// It is blended over plains and swamps alike by its alpha, so swamp exits stay swamps.
+  pub exit: image::Rgba<u8>,
+  /// The radius of rounded corners, in room cells; at most 0.5
+  pub corner_radius: f32,
+}
+
+impl Default for TerrainStyle {
+  fn default() -> Self {
+    Self {
+      plain: image::Rgba([43, 43, 43, 255]),
+      swamp: image::Rgba([41, 52, 22, 255]),
+      wall: image::Rgba([17, 17, 17, 255]),
+      natural_wall: image::Rgba([6, 6, 6, 255]),
+      exit: image::Rgba([255, 255, 255, 18]),
+      corner_radius: 0.5,
+    }
+  }
+}
+
+/// Draws a whole room's terrain on a default-sized image
+pub fn draw_room_terrain(imgbuf: &mut OutputImage, terrain: &LocalRoomTerrain, style: &TerrainStyle) {
+  draw_room_terrain_with_scale_factor(imgbuf, terrain, style, DEFAULT_SCALE_FACTOR)
+}
+
+/// Draws a whole room's terrain on an image with user-supplied scaling
+pub fn draw_room_terrain_with_scale_factor(imgbuf: &mut OutputImage, terrain: &LocalRoomTerrain, style: &TerrainStyle, scale_factor: u32) {
+  RenderContext::with_scale_factor(scale_factor).draw_room_terrain(imgbuf, terrain, style)
+}
+
+/// Returns whether a tile is on the edge of the room
+fn is_edge(x: i32, y: i32) -> bool {
+  let last = ROOM_SIZE as i32 - 1;
+  (x == 0) | (y == 0) | (x == last) | (y == last)
+}
+
+/// Returns how much of a point within a tile is covered by a kind of terrain, with rounded contours
+///
+/// `is_solid` says whether a tile is of that kind; `(u, v)` is the point's offset
+/// within the tile, from 0.0 to 1.0, and `pixels` is the size of a cell in pixels.
+/// Convex corners are rounded off, and concave corners are filled in with a fillet.
+fn contour_coverage(is_solid: impl Fn(i32, i32) -> bool, x: i32, y: i32, u: f32, v: f32, radius: f32, pixels: f32) -> f32 {
+  // Work in the quadrant nearest the point, mirrored so the corner is at (0, 0)
+  let (dx, u) = if u < 0.5 { (-1, u) } else { (1, 1.0 - u) };
+  let (dy, v) = if v < 0.5 { (-1, v) } else { (1, 1.0 - v) };
+  let solid = is_solid(x, y);
+  let horizontal = is_solid(x + dx, y);
+  let vertical = is_solid(x, y + dy);
+  let diagonal = is_solid(x + dx, y + dy);
+
+  let in_corner = (u < radius) & (v < radius);
+  let distance = ((u - radius).powi(2) + (v - radius).powi(2)).sqrt();
+  let edge = if solid & !horizontal & !vertical & in_corner {
+    // A convex corner: solid inside the arc
+    (radius - distance) * pixels + 0.5
+  } else if !solid & horizontal & vertical & diagonal & in_corner {
+    // A concave corner: solid outside the arc
+    (distance - radius) * pixels + 0.5
+  } else if solid {
+    1.0
+  } else {
+    0.0
+  };
+  edge.clamp(0.0, 1.0)
+}
+
+/// Mixes two opaque colors by a coverage fraction
+fn mix_coverage(base: image::Rgba<u8>, over: image::Rgba<u8>, coverage: f32) -> image::Rgba<u8> {
+  let channel = |i: usize| lerp(base[i] as f32, over[i] as f32, coverage).round() as u8;
+  image::Rgba([channel(0), channel(1), channel(2), channel(3)])
+}
+
+impl RenderContext {
+  /// Draws a whole room's terrain with smooth, rounded wall and swamp contours, like the game client
+  ///
+  /// Walls along the room edge are drawn darker than inner walls, and passable
+  /// edge tiles are highlighted as exits. Tiles outside the room count as walls,
+  /// so contours don't round off at the edge.
+  pub fn draw_room_terrain(&self, imgbuf: &mut OutputImage, terrain: &LocalRoomTerrain, style: &TerrainStyle) {
+    let size = ROOM_SIZE as i32;
+    let in_room = |x: i32, y: i32| (0..size).contains(&x) & (0..size).contains(&y);
+    let tile = |x: i32, y: i32| {
+      in_room(x, y).then(|| terrain.get_xy(RoomXY::checked_new(x as u8, y as u8).expect("tile should be in the room")))
+    };
+    let is_wall = |x: i32, y: i32| tile(x, y).map_or(true, |tile| tile == Terrain::Wall);
+    let is_swamp = |x: i32, y: i32| tile(x, y) == Some(Terrain::Swamp);
+
+    let pixels = self.scale_factor as f32;
+    let radius = style.corner_radius.clamp(0.0, 0.5);
+    let cols = self.room_max_cols.min(ROOM_SIZE as u32) as i32;
+    let rows = self.room_max_rows.min(ROOM_SIZE as u32) as i32;
+    let exit_color = image::Rgba([style.exit[0], style.exit[1], style.exit[2], 255]);
+    let exit_tint = style.exit[3] as f32 / 255.0;
+
+    for (px, py, pixel) in imgbuf.enumerate_pixels_mut() {
+      let fx = (px as f32 - self.border_width as f32 + 0.5) / pixels;
+      let fy = (py as f32 - self.border_width as f32 + 0.5) / pixels;
+      let (x, y) = (fx.floor() as i32, fy.floor() as i32);
+      if (x < 0) | (y < 0) | (x >= cols) | (y >= rows) {
+        continue;
+      }
+      let (u, v) = (fx - x as f32, fy - y as f32);
+
+      let swamp = contour_coverage(is_swamp, x, y, u, v, radius, pixels);
+      let wall = contour_coverage(is_wall, x, y, u, v, radius, pixels);
+      let wall_color = if is_edge(x, y) { style.natural_wall } else { style.wall };
+
+      // Tint exits after the swamp layer, so swamps on the edge are highlighted too
+      let mut ground = mix_coverage(style.plain, style.swamp, swamp);
+      if is_edge(x, y) & !is_wall(x, y) {
+        ground = mix_coverage(ground, exit_color, exit_tint);
+      }
+      *pixel = mix_coverage(ground, wall_color, wall);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn exits_are_tinted_over_plains_and_swamps() {
+    let mut bits = [0u8; 2500];
+    bits[10 * 50] = 2;
+    bits[10 * 50 + 10] = 2;
+    let terrain = LocalRoomTerrain::new_from_bits(Box::new(bits));
+    let ctx = RenderContext::with_scale_factor(10);
+    let mut imgbuf = ctx.create_image();
+    ctx.draw_room_terrain(&mut imgbuf, &terrain, &TerrainStyle::default());
+    let pixel_at = |x: f32, y: f32| {
+      let (px, py) = ctx.room_to_pixel(x, y);
+      *imgbuf.get_pixel(px as u32, py as u32)
+    };
+
+    let style = TerrainStyle::default();
+    assert_eq!(pixel_at(0.0, 20.0), image::Rgba([58, 58, 58, 255]));
+    assert_eq!(pixel_at(20.0, 20.0), style.plain);
+    assert_eq!(pixel_at(10.0, 10.0), style.swamp);
+    let swamp_exit = pixel_at(0.0, 10.0);
+    assert_ne!(swamp_exit, style.swamp);
+    assert!((0..3).all(|i| swamp_exit[i] > style.swamp[i]));
+    assert_eq!(swamp_exit, mix_coverage(style.swamp, image::Rgba([255, 255, 255, 255]), 18.0 / 255.0));
+  }
+}
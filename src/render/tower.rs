@@ -0,0 +1,85 @@
+use screeps::constants::{TOWER_POWER_ATTACK, TOWER_POWER_HEAL, TOWER_POWER_REPAIR};
+use screeps::local::RoomXY;
+
+use super::*;
+
+/// The action a tower overlay shows the strength of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TowerAction {
+  Attack,
+  Heal,
+  Repair,
+}
+
+impl TowerAction {
+  /// The hits a single tower attacks, heals or repairs per tick at optimal range
+  pub fn power(self) -> u32 {
+    match self {
+      TowerAction::Attack => TOWER_POWER_ATTACK,
+      TowerAction::Heal   => TOWER_POWER_HEAL,
+      TowerAction::Repair => TOWER_POWER_REPAIR,
+    }
+  }
+}
+
+/// Returns one [RangeSource] per tower, weighted by the power of an action
+pub fn tower_sources(towers: &[RoomXY], action: TowerAction) -> Vec<RangeSource> {
+  towers.iter()
+    .map(|tower| RangeSource::tower(*tower).weight(action.power() as f64))
+    .collect()
+}
+
+/// Returns the hits per tick all towers together apply to a tile with an action, after range falloff
+pub fn tower_power_at(towers: &[RoomXY], xy: RoomXY, action: TowerAction) -> f64 {
+  towers.iter()
+    .filter_map(|tower| RangeSource::tower(*tower).weight(action.power() as f64).value(xy))
+    .sum()
+}
+
+/// Draws the combined tower strength at every tile on a default-sized image
+pub fn draw_tower_effectiveness(imgbuf: &mut OutputImage, towers: &[RoomXY], action: TowerAction, colormap: &dyn Colormap, options: &HeatmapOptions) -> Option<(f64, f64)> {
+  draw_tower_effectiveness_with_scale_factor(imgbuf, towers, action, colormap, options, DEFAULT_SCALE_FACTOR)
+}
+
+/// Draws the combined tower strength at every tile on an image with user-supplied scaling
+pub fn draw_tower_effectiveness_with_scale_factor(imgbuf: &mut OutputImage, towers: &[RoomXY], action: TowerAction, colormap: &dyn Colormap, options: &HeatmapOptions, scale_factor: u32) -> Option<(f64, f64)> {
+  RenderContext::with_scale_factor(scale_factor).draw_tower_effectiveness(imgbuf, towers, action, colormap, options)
+}
+
+impl RenderContext {
+  /// Draws the hits per tick all towers together apply to each tile with an action
+  ///
+  /// Uses the game's range falloff for each tower and adds overlapping towers
+  /// together. Turn on [HeatmapOptions::draw_labels] to write the totals in each
+  /// tile, for checking rampart placements. Returns the range of totals, or `None` without towers.
+  pub fn draw_tower_effectiveness(&self, imgbuf: &mut OutputImage, towers: &[RoomXY], action: TowerAction, colormap: &dyn Colormap, options: &HeatmapOptions) -> Option<(f64, f64)> {
+    self.draw_range_sources(imgbuf, &tower_sources(towers, action), colormap, options)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn tower_power_matches_the_game() {
+    let tower = [xy(5, 25)];
+    let expected = [
+      (TowerAction::Attack, [600.0, 450.0, 150.0]),
+      (TowerAction::Heal,   [400.0, 300.0, 100.0]),
+      (TowerAction::Repair, [800.0, 600.0, 200.0]),
+    ];
+    for (action, amounts) in expected {
+      for (range, amount) in [5, 10, 20].into_iter().zip(amounts) {
+        assert_eq!(tower_power_at(&tower, xy(5 + range, 25), action), amount, "{:?} at range {}", action, range);
+      }
+    }
+  }
+
+  #[test]
+  fn tower_power_adds_up_past_falloff_range() {
+    let towers = [xy(0, 0), xy(49, 0)];
+    assert_eq!(tower_power_at(&towers, xy(0, 49), TowerAction::Attack), 300.0);
+    assert_eq!(tower_power_at(&[], xy(0, 49), TowerAction::Attack), 0.0);
+  }
+}
@@ -0,0 +1,815 @@
+use serde::Deserialize;
+
+use super::*;
+
+/// The dash pattern used when stroking lines and outlines
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LineDrawStyle {
+  #[default]
+  Solid,
+  Dashed,
+  Dotted,
+}
+
+/// The horizontal alignment of text relative to its position
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TextAlign {
+  #[default]
+  Center,
+  Left,
+  Right,
+}
+
+/// The size of text, either in room cells or as a CSS-style font string such as `"0.7 serif"`
+///
+/// Only the size in a custom font string is used; all text is drawn with the
+/// bundled monospace font. Sizes given in pixels, such as `"20px"`, are
+/// converted to room cells using the image's pixels per cell.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum FontStyle {
+  Size(f32),
+  Custom(String),
+}
+
+impl FontStyle {
+  /// The font size in room cells, on an image with the given number of pixels per cell
+  pub fn size(&self, scale_factor: f32) -> f32 {
+    match self {
+      FontStyle::Size(size) => *size,
+      FontStyle::Custom(font) => font.split_whitespace()
+        .find_map(|part| match part.strip_suffix("px") {
+          Some(pixels) => pixels.parse::<f32>().ok().map(|pixels| pixels / scale_factor),
+          None => part.parse::<f32>().ok(),
+        })
+        .unwrap_or(DEFAULT_FONT_SIZE),
+    }
+  }
+}
+
+/// The font size used by the game when a text style doesn't specify one
+const DEFAULT_FONT_SIZE: f32 = 0.5;
+
+/// Style options for [RenderContext::draw_circle], matching the game's `RoomVisual.circle`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CircleStyle {
+  pub radius: Option<f32>,
+  pub fill: Option<String>,
+  pub opacity: Option<f32>,
+  pub stroke: Option<String>,
+  pub stroke_width: Option<f32>,
+}
+
+impl CircleStyle {
+  pub fn radius(mut self, val: f32) -> CircleStyle {
+    self.radius = Some(val);
+    self
+  }
+
+  pub fn fill(mut self, val: &str) -> CircleStyle {
+    self.fill = Some(val.to_string());
+    self
+  }
+
+  pub fn opacity(mut self, val: f32) -> CircleStyle {
+    self.opacity = Some(val);
+    self
+  }
+
+  pub fn stroke(mut self, val: &str) -> CircleStyle {
+    self.stroke = Some(val.to_string());
+    self
+  }
+
+  pub fn stroke_width(mut self, val: f32) -> CircleStyle {
+    self.stroke_width = Some(val);
+    self
+  }
+}
+
+/// Style options for [RenderContext::draw_line], matching the game's `RoomVisual.line`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineStyle {
+  pub width: Option<f32>,
+  pub color: Option<String>,
+  pub opacity: Option<f32>,
+  #[serde(default)]
+  pub line_style: LineDrawStyle,
+}
+
+impl LineStyle {
+  pub fn width(mut self, val: f32) -> LineStyle {
+    self.width = Some(val);
+    self
+  }
+
+  pub fn color(mut self, val: &str) -> LineStyle {
+    self.color = Some(val.to_string());
+    self
+  }
+
+  pub fn opacity(mut self, val: f32) -> LineStyle {
+    self.opacity = Some(val);
+    self
+  }
+
+  pub fn line_style(mut self, val: LineDrawStyle) -> LineStyle {
+    self.line_style = val;
+    self
+  }
+}
+
+/// Style options for [RenderContext::draw_rect], matching the game's `RoomVisual.rect`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RectStyle {
+  pub fill: Option<String>,
+  pub opacity: Option<f32>,
+  pub stroke: Option<String>,
+  pub stroke_width: Option<f32>,
+  #[serde(default)]
+  pub line_style: LineDrawStyle,
+}
+
+impl RectStyle {
+  pub fn fill(mut self, val: &str) -> RectStyle {
+    self.fill = Some(val.to_string());
+    self
+  }
+
+  pub fn opacity(mut self, val: f32) -> RectStyle {
+    self.opacity = Some(val);
+    self
+  }
+
+  pub fn stroke(mut self, val: &str) -> RectStyle {
+    self.stroke = Some(val.to_string());
+    self
+  }
+
+  pub fn stroke_width(mut self, val: f32) -> RectStyle {
+    self.stroke_width = Some(val);
+    self
+  }
+
+  pub fn line_style(mut self, val: LineDrawStyle) -> RectStyle {
+    self.line_style = val;
+    self
+  }
+}
+
+/// Style options for [RenderContext::draw_poly], matching the game's `RoomVisual.poly`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolyStyle {
+  pub fill: Option<String>,
+  pub opacity: Option<f32>,
+  pub stroke: Option<String>,
+  pub stroke_width: Option<f32>,
+  #[serde(default)]
+  pub line_style: LineDrawStyle,
+}
+
+impl PolyStyle {
+  pub fn fill(mut self, val: &str) -> PolyStyle {
+    self.fill = Some(val.to_string());
+    self
+  }
+
+  pub fn opacity(mut self, val: f32) -> PolyStyle {
+    self.opacity = Some(val);
+    self
+  }
+
+  pub fn stroke(mut self, val: &str) -> PolyStyle {
+    self.stroke = Some(val.to_string());
+    self
+  }
+
+  pub fn stroke_width(mut self, val: f32) -> PolyStyle {
+    self.stroke_width = Some(val);
+    self
+  }
+
+  pub fn line_style(mut self, val: LineDrawStyle) -> PolyStyle {
+    self.line_style = val;
+    self
+  }
+}
+
+/// Style options for [RenderContext::draw_text], matching the game's `RoomVisual.text`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextStyle {
+  pub color: Option<String>,
+  pub font: Option<FontStyle>,
+  pub stroke: Option<String>,
+  pub stroke_width: Option<f32>,
+  pub background_color: Option<String>,
+  pub background_padding: Option<f32>,
+  #[serde(default)]
+  pub align: TextAlign,
+  pub opacity: Option<f32>,
+}
+
+impl TextStyle {
+  pub fn color(mut self, val: &str) -> TextStyle {
+    self.color = Some(val.to_string());
+    self
+  }
+
+  pub fn font(mut self, val: f32) -> TextStyle {
+    self.font = Some(FontStyle::Size(val));
+    self
+  }
+
+  pub fn custom_font(mut self, val: &str) -> TextStyle {
+    self.font = Some(FontStyle::Custom(val.to_string()));
+    self
+  }
+
+  pub fn stroke(mut self, val: &str) -> TextStyle {
+    self.stroke = Some(val.to_string());
+    self
+  }
+
+  pub fn stroke_width(mut self, val: f32) -> TextStyle {
+    self.stroke_width = Some(val);
+    self
+  }
+
+  pub fn background_color(mut self, val: &str) -> TextStyle {
+    self.background_color = Some(val.to_string());
+    self
+  }
+
+  pub fn background_padding(mut self, val: f32) -> TextStyle {
+    self.background_padding = Some(val);
+    self
+  }
+
+  pub fn align(mut self, val: TextAlign) -> TextStyle {
+    self.align = val;
+    self
+  }
+
+  pub fn opacity(mut self, val: f32) -> TextStyle {
+    self.opacity = Some(val);
+    self
+  }
+}
+
+/// A single drawing call, in the wire format the game uses to store room visuals
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "t")]
+pub enum Visual {
+  #[serde(rename = "c")]
+  Circle {
+    x: f32,
+    y: f32,
+    #[serde(rename = "s")]
+    style: Option<CircleStyle>,
+  },
+  #[serde(rename = "l")]
+  Line {
+    x1: f32,
+    y1: f32,
+    x2: f32,
+    y2: f32,
+    #[serde(rename = "s")]
+    style: Option<LineStyle>,
+  },
+  #[serde(rename = "r")]
+  Rect {
+    x: f32,
+    y: f32,
+    #[serde(rename = "w")]
+    width: f32,
+    #[serde(rename = "h")]
+    height: f32,
+    #[serde(rename = "s")]
+    style: Option<RectStyle>,
+  },
+  #[serde(rename = "p")]
+  Poly {
+    points: Vec<(f32, f32)>,
+    #[serde(rename = "s")]
+    style: Option<PolyStyle>,
+  },
+  #[serde(rename = "t")]
+  Text {
+    text: String,
+    x: f32,
+    y: f32,
+    #[serde(rename = "s")]
+    style: Option<TextStyle>,
+  },
+}
+
+impl TryFrom<&screeps::objects::Visual> for Visual {
+    type Error = serde_json::Error;
+
+    #[inline]
+    fn try_from(visual: &screeps::objects::Visual) -> Result<Visual, Self::Error> {
+        // The game API's visual types can only be serialized, so convert through the wire format
+        serde_json::from_value(serde_json::to_value(visual)?)
+    }
+}
+
+/// Parses a CSS-style color: `#rgb`, `#rrggbb`, `#rrggbbaa`, `rgb(..)`, `rgba(..)` or a common color name
+pub fn parse_color(color: &str) -> Option<image::Rgba<u8>> {
+  let color = color.trim().to_ascii_lowercase();
+
+  if let Some(hex) = color.strip_prefix('#') {
+    let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+    let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    return match hex.len() {
+      3 => Some(image::Rgba([digit(0)? * 17, digit(1)? * 17, digit(2)? * 17, 255])),
+      6 => Some(image::Rgba([byte(0)?, byte(2)?, byte(4)?, 255])),
+      8 => Some(image::Rgba([byte(0)?, byte(2)?, byte(4)?, byte(6)?])),
+      _ => None,
+    };
+  }
+
+  if let Some(args) = color.strip_prefix("rgba(").or_else(|| color.strip_prefix("rgb(")).and_then(|rest| rest.strip_suffix(')')) {
+    let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+    let channel = |i: usize| parts.get(i)?.parse::<f32>().ok().map(|v| v.clamp(0.0, 255.0) as u8);
+    let alpha = match parts.get(3) {
+      Some(a) => (a.parse::<f32>().ok()?.clamp(0.0, 1.0) * 255.0).round() as u8,
+      None => 255,
+    };
+    return Some(image::Rgba([channel(0)?, channel(1)?, channel(2)?, alpha]));
+  }
+
+  let [r, g, b, a] = match color.as_str() {
+    "white"       => [255, 255, 255, 255],
+    "black"       => [0, 0, 0, 255],
+    "red"         => [255, 0, 0, 255],
+    "green"       => [0, 128, 0, 255],
+    "lime"        => [0, 255, 0, 255],
+    "blue"        => [0, 0, 255, 255],
+    "yellow"      => [255, 255, 0, 255],
+    "cyan"        => [0, 255, 255, 255],
+    "magenta"     => [255, 0, 255, 255],
+    "orange"      => [255, 165, 0, 255],
+    "purple"      => [128, 0, 128, 255],
+    "grey" | "gray" => [128, 128, 128, 255],
+    "transparent" => [0, 0, 0, 0],
+    _ => return None,
+  };
+  Some(image::Rgba([r, g, b, a]))
+}
+
+/// Formats a color as a `#rrggbbaa` string, for use in visual styles
+pub(super) fn rgba_to_hex(color: image::Rgba<u8>) -> String {
+  let [r, g, b, a] = color.0;
+  format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+}
+
+/// A per-pixel coverage buffer for a region of an image
+///
+/// Shapes are accumulated into the mask first and then blended onto the image
+/// once, so overlapping parts of a translucent shape don't darken each other.
+pub(super) struct CoverageMask {
+  x0: u32,
+  y0: u32,
+  width: u32,
+  height: u32,
+  data: Vec<f32>,
+}
+
+impl CoverageMask {
+  /// Creates a mask covering the given pixel-space bounds, clipped to the image
+  pub(super) fn new(imgbuf: &OutputImage, min: (f32, f32), max: (f32, f32)) -> Option<Self> {
+    let x0 = min.0.floor().max(0.0) as u32;
+    let y0 = min.1.floor().max(0.0) as u32;
+    let x1 = (max.0.ceil().max(0.0) as u32).min(imgbuf.width());
+    let y1 = (max.1.ceil().max(0.0) as u32).min(imgbuf.height());
+    if (x0 >= x1) | (y0 >= y1) {
+      return None;
+    }
+
+    let (width, height) = (x1 - x0, y1 - y0);
+    Some(Self { x0, y0, width, height, data: vec![0.0; (width * height) as usize] })
+  }
+
+  /// Raises the coverage of every pixel to the value computed at its center
+  fn fill_with(&mut self, coverage: impl Fn(f32, f32) -> f32) {
+    self.fill_region_with(0..self.width, 0..self.height, coverage);
+  }
+
+  /// Raises the coverage of the pixels in a range of mask columns and rows to the value computed at their centers
+  fn fill_region_with(&mut self, columns: std::ops::Range<u32>, rows: std::ops::Range<u32>, coverage: impl Fn(f32, f32) -> f32) {
+    for j in rows {
+      for i in columns.clone() {
+        let px = (self.x0 + i) as f32 + 0.5;
+        let py = (self.y0 + j) as f32 + 0.5;
+        let value = &mut self.data[(j * self.width + i) as usize];
+        *value = value.max(coverage(px, py).clamp(0.0, 1.0));
+      }
+    }
+  }
+
+  /// Returns the mask columns and rows within pixel-space bounds
+  fn region(&self, min: (f32, f32), max: (f32, f32)) -> (std::ops::Range<u32>, std::ops::Range<u32>) {
+    let clip = |value: f32, origin: u32, size: u32| ((value - origin as f32).max(0.0) as u32).min(size);
+    let columns = clip(min.0.floor(), self.x0, self.width)..clip(max.0.ceil(), self.x0, self.width);
+    let rows = clip(min.1.floor(), self.y0, self.height)..clip(max.1.ceil(), self.y0, self.height);
+    (columns, rows)
+  }
+
+  /// Raises the coverage under a stroked polyline
+  ///
+  /// Each segment is rasterized only within its own bounds, so the cost grows
+  /// with the length of the line rather than the area of its bounding box.
+  pub(super) fn stroke_polyline(&mut self, points: &[(f32, f32)], half_width: f32, dash: Option<(f32, f32)>) {
+    let mut travelled = 0.0;
+    for segment in points.windows(2) {
+      let (from, to) = (segment[0], segment[1]);
+      let (min, max) = bounds(segment, half_width + 1.0);
+      let (columns, rows) = self.region(min, max);
+      self.fill_region_with(columns, rows, |px, py| segment_coverage(from, to, half_width, dash, travelled, px, py));
+      travelled += ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt();
+    }
+  }
+
+  /// Raises the coverage under a filled circle
+  pub(super) fn fill_disc(&mut self, center: (f32, f32), radius: f32) {
+    let (columns, rows) = self.region(
+      (center.0 - radius - 1.0, center.1 - radius - 1.0),
+      (center.0 + radius + 1.0, center.1 + radius + 1.0),
+    );
+    self.fill_region_with(columns, rows, |px, py| radius - ((px - center.0).powi(2) + (py - center.1).powi(2)).sqrt() + 0.5);
+  }
+
+  /// Raises the coverage under a filled polygon, using the even-odd rule
+  pub(super) fn fill_polygon(&mut self, points: &[(f32, f32)]) {
+    if points.is_empty() {
+      return;
+    }
+    let (min, max) = bounds(points, 1.0);
+    let (columns, rows) = self.region(min, max);
+    // Sample a 4x4 grid within each pixel to smooth the edges
+    self.fill_region_with(columns, rows, |px, py| {
+      let mut hits = 0;
+      for sx in 0..4 {
+        for sy in 0..4 {
+          let sample_x = px - 0.375 + sx as f32 * 0.25;
+          let sample_y = py - 0.375 + sy as f32 * 0.25;
+          if polygon_contains(points, sample_x, sample_y) {
+            hits += 1;
+          }
+        }
+      }
+      hits as f32 / 16.0
+    });
+  }
+
+  /// Raises the coverage of a single image pixel
+  fn add(&mut self, x: i32, y: i32, coverage: f32) {
+    if (x < self.x0 as i32) | (y < self.y0 as i32) {
+      return;
+    }
+    let (i, j) = (x as u32 - self.x0, y as u32 - self.y0);
+    if (i < self.width) & (j < self.height) {
+      let value = &mut self.data[(j * self.width + i) as usize];
+      *value = value.max(coverage.clamp(0.0, 1.0));
+    }
+  }
+
+  /// Returns the coverage of a single image pixel
+  fn get(&self, x: i32, y: i32) -> f32 {
+    if (x < self.x0 as i32) | (y < self.y0 as i32) {
+      return 0.0;
+    }
+    let (i, j) = (x as u32 - self.x0, y as u32 - self.y0);
+    if (i < self.width) & (j < self.height) {
+      self.data[(j * self.width + i) as usize]
+    } else {
+      0.0
+    }
+  }
+
+  /// Blends the color onto the image, weighted by coverage and opacity
+  pub(super) fn composite(&self, imgbuf: &mut OutputImage, color: image::Rgba<u8>, opacity: f32) {
+    use image::Pixel;
+
+    for j in 0..self.height {
+      for i in 0..self.width {
+        let coverage = self.data[(j * self.width + i) as usize];
+        if coverage <= 0.0 {
+          continue;
+        }
+        let alpha = (color[3] as f32 * coverage * opacity.clamp(0.0, 1.0)).round() as u8;
+        let [r, g, b, _] = color.0;
+        imgbuf.get_pixel_mut(self.x0 + i, self.y0 + j).blend(&image::Rgba([r, g, b, alpha]));
+      }
+    }
+  }
+}
+
+/// Returns the (on, off) lengths in pixels of a dash pattern for a stroke of the given width
+pub(super) fn dash_pattern(line_style: LineDrawStyle, width: f32) -> Option<(f32, f32)> {
+  let width = width.max(1.0);
+  match line_style {
+    LineDrawStyle::Solid  => None,
+    LineDrawStyle::Dashed => Some((width * 4.0, width * 2.0)),
+    LineDrawStyle::Dotted => Some((width, width * 1.5)),
+  }
+}
+
+/// Computes the coverage of a pixel by one stroked segment of a polyline
+///
+/// `travelled` is the length of the polyline before this segment, which keeps
+/// dash patterns continuous across segments.
+fn segment_coverage((ax, ay): (f32, f32), (bx, by): (f32, f32), half_width: f32, dash: Option<(f32, f32)>, travelled: f32, px: f32, py: f32) -> f32 {
+  let (dx, dy) = (bx - ax, by - ay);
+  let length_sq = dx * dx + dy * dy;
+  let t = if length_sq > 0.0 {
+    (((px - ax) * dx + (py - ay) * dy) / length_sq).clamp(0.0, 1.0)
+  } else {
+    0.0
+  };
+  let (cx, cy) = (ax + t * dx, ay + t * dy);
+  let distance = ((px - cx).powi(2) + (py - cy).powi(2)).sqrt();
+  let coverage = half_width - distance + 0.5;
+
+  match dash {
+    Some((on, off)) => {
+      let along = (travelled + t * length_sq.sqrt()) % (on + off);
+      if along < on {
+        coverage.min(along.min(on - along) + 0.5)
+      } else {
+        0.0
+      }
+    },
+    None => coverage,
+  }
+}
+
+/// Returns whether a point is inside a polygon, using the even-odd rule
+fn polygon_contains(points: &[(f32, f32)], px: f32, py: f32) -> bool {
+  let mut inside = false;
+  let mut j = points.len() - 1;
+  for i in 0..points.len() {
+    let ((xi, yi), (xj, yj)) = (points[i], points[j]);
+    if ((yi > py) != (yj > py)) && (px < (xj - xi) * (py - yi) / (yj - yi) + xi) {
+      inside = !inside;
+    }
+    j = i;
+  }
+  inside
+}
+
+/// Returns the bounding box of a set of points, grown by a margin
+pub(super) fn bounds(points: &[(f32, f32)], margin: f32) -> ((f32, f32), (f32, f32)) {
+  let mut min = (f32::INFINITY, f32::INFINITY);
+  let mut max = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+  for (x, y) in points {
+    min = (min.0.min(*x), min.1.min(*y));
+    max = (max.0.max(*x), max.1.max(*y));
+  }
+  ((min.0 - margin, min.1 - margin), (max.0 + margin, max.1 + margin))
+}
+
+impl RenderContext {
+  /// Converts a room coordinate, which may be fractional, to a pixel-space coordinate
+  ///
+  /// As in the game, whole-number room coordinates are the centers of cells.
+  pub fn room_to_pixel(&self, x: f32, y: f32) -> (f32, f32) {
+    let scale_factor = self.scale_factor as f32;
+    let border_width = self.border_width as f32;
+    (border_width + (x + 0.5) * scale_factor, border_width + (y + 0.5) * scale_factor)
+  }
+
+  /// Draws any [Visual]
+  pub fn draw_visual(&self, imgbuf: &mut OutputImage, visual: &Visual) {
+    match visual {
+      Visual::Circle { x, y, style } => self.draw_circle(imgbuf, *x, *y, &style.clone().unwrap_or_default()),
+      Visual::Line { x1, y1, x2, y2, style } => self.draw_line(imgbuf, (*x1, *y1), (*x2, *y2), &style.clone().unwrap_or_default()),
+      Visual::Rect { x, y, width, height, style } => self.draw_rect(imgbuf, *x, *y, *width, *height, &style.clone().unwrap_or_default()),
+      Visual::Poly { points, style } => self.draw_poly(imgbuf, points, &style.clone().unwrap_or_default()),
+      Visual::Text { text, x, y, style } => self.draw_text(imgbuf, text, *x, *y, &style.clone().unwrap_or_default()),
+    }
+  }
+
+  /// Strokes a polyline given in pixel-space coordinates
+  fn stroke_polyline(&self, imgbuf: &mut OutputImage, points: &[(f32, f32)], width: f32, color: image::Rgba<u8>, opacity: f32, line_style: LineDrawStyle) {
+    if points.is_empty() {
+      return;
+    }
+    let half_width = width / 2.0;
+    let dash = dash_pattern(line_style, width);
+    let (min, max) = bounds(points, half_width + 1.0);
+    if let Some(mut mask) = CoverageMask::new(imgbuf, min, max) {
+      mask.stroke_polyline(points, half_width, dash);
+      mask.composite(imgbuf, color, opacity);
+    }
+  }
+
+  /// Draws a circle centered on a room coordinate, like `RoomVisual.circle`
+  pub fn draw_circle(&self, imgbuf: &mut OutputImage, x: f32, y: f32, style: &CircleStyle) {
+    let scale_factor = self.scale_factor as f32;
+    let (cx, cy) = self.room_to_pixel(x, y);
+    let radius = style.radius.unwrap_or(0.15) * scale_factor;
+    let opacity = style.opacity.unwrap_or(0.5);
+    let stroke_width = style.stroke_width.unwrap_or(0.1) * scale_factor;
+    let extent = radius + stroke_width / 2.0 + 1.0;
+    let distance = |px: f32, py: f32| ((px - cx).powi(2) + (py - cy).powi(2)).sqrt();
+
+    if let Some(fill) = parse_color(style.fill.as_deref().unwrap_or("#ffffff")) {
+      if let Some(mut mask) = CoverageMask::new(imgbuf, (cx - extent, cy - extent), (cx + extent, cy + extent)) {
+        mask.fill_disc((cx, cy), radius);
+        mask.composite(imgbuf, fill, opacity);
+      }
+    }
+
+    if let Some(stroke) = style.stroke.as_deref().and_then(parse_color) {
+      if let Some(mut mask) = CoverageMask::new(imgbuf, (cx - extent, cy - extent), (cx + extent, cy + extent)) {
+        mask.fill_with(|px, py| stroke_width / 2.0 - (distance(px, py) - radius).abs() + 0.5);
+        mask.composite(imgbuf, stroke, opacity);
+      }
+    }
+  }
+
+  /// Draws a line between two room coordinates, like `RoomVisual.line`
+  pub fn draw_line(&self, imgbuf: &mut OutputImage, from: (f32, f32), to: (f32, f32), style: &LineStyle) {
+    let Some(color) = parse_color(style.color.as_deref().unwrap_or("#ffffff")) else { return };
+    let width = style.width.unwrap_or(0.1) * self.scale_factor as f32;
+    let points = [self.room_to_pixel(from.0, from.1), self.room_to_pixel(to.0, to.1)];
+    self.stroke_polyline(imgbuf, &points, width, color, style.opacity.unwrap_or(0.5), style.line_style);
+  }
+
+  /// Draws a rectangle with its top-left corner at a room coordinate, like `RoomVisual.rect`
+  pub fn draw_rect(&self, imgbuf: &mut OutputImage, x: f32, y: f32, width: f32, height: f32, style: &RectStyle) {
+    let scale_factor = self.scale_factor as f32;
+    let (left, top) = self.room_to_pixel(x, y);
+    let (right, bottom) = (left + width * scale_factor, top + height * scale_factor);
+    let opacity = style.opacity.unwrap_or(0.5);
+
+    if let Some(fill) = parse_color(style.fill.as_deref().unwrap_or("#ffffff")) {
+      if let Some(mut mask) = CoverageMask::new(imgbuf, (left.min(right), top.min(bottom)), (left.max(right), top.max(bottom))) {
+        let (x_min, x_max) = (left.min(right), left.max(right));
+        let (y_min, y_max) = (top.min(bottom), top.max(bottom));
+        mask.fill_with(|px, py| {
+          let horizontal = (px + 0.5).min(x_max) - (px - 0.5).max(x_min);
+          let vertical = (py + 0.5).min(y_max) - (py - 0.5).max(y_min);
+          horizontal.clamp(0.0, 1.0) * vertical.clamp(0.0, 1.0)
+        });
+        mask.composite(imgbuf, fill, opacity);
+      }
+    }
+
+    if let Some(stroke) = style.stroke.as_deref().and_then(parse_color) {
+      let stroke_width = style.stroke_width.unwrap_or(0.1) * scale_factor;
+      let outline = [(left, top), (right, top), (right, bottom), (left, bottom), (left, top)];
+      self.stroke_polyline(imgbuf, &outline, stroke_width, stroke, opacity, style.line_style);
+    }
+  }
+
+  /// Draws a polygon through a list of room coordinates, like `RoomVisual.poly`
+  ///
+  /// The outline is stroked as an open path; close it by repeating the first point.
+  pub fn draw_poly(&self, imgbuf: &mut OutputImage, points: &[(f32, f32)], style: &PolyStyle) {
+    if points.is_empty() {
+      return;
+    }
+    let scale_factor = self.scale_factor as f32;
+    let points: Vec<(f32, f32)> = points.iter().map(|(x, y)| self.room_to_pixel(*x, *y)).collect();
+    let opacity = style.opacity.unwrap_or(0.5);
+
+    if let Some(fill) = style.fill.as_deref().and_then(parse_color) {
+      let (min, max) = bounds(&points, 1.0);
+      if let Some(mut mask) = CoverageMask::new(imgbuf, min, max) {
+        mask.fill_polygon(&points);
+        mask.composite(imgbuf, fill, opacity);
+      }
+    }
+
+    if let Some(stroke) = parse_color(style.stroke.as_deref().unwrap_or("#ffffff")) {
+      let stroke_width = style.stroke_width.unwrap_or(0.1) * scale_factor;
+      self.stroke_polyline(imgbuf, &points, stroke_width, stroke, opacity, style.line_style);
+    }
+  }
+
+  /// Draws text with its baseline at a room coordinate, like `RoomVisual.text`
+  pub fn draw_text(&self, imgbuf: &mut OutputImage, text: &str, x: f32, y: f32, style: &TextStyle) {
+    let scale_factor = self.scale_factor as f32;
+    let font = &assets_data::FREE_MONO_FONT;
+    let font_size = style.font.as_ref().map_or(DEFAULT_FONT_SIZE, |font| font.size(scale_factor)) * scale_factor;
+    let scale = rusttype::Scale::uniform(font_size);
+    let v_metrics = font.v_metrics(scale);
+    let opacity = style.opacity.unwrap_or(1.0);
+
+    let text_width = font.layout(text, scale, rusttype::point(0.0, 0.0))
+      .last()
+      .map_or(0.0, |glyph| glyph.position().x + glyph.unpositioned().h_metrics().advance_width);
+    let (anchor_x, baseline) = self.room_to_pixel(x, y);
+    let left = match style.align {
+      TextAlign::Left   => anchor_x,
+      TextAlign::Center => anchor_x - text_width / 2.0,
+      TextAlign::Right  => anchor_x - text_width,
+    };
+
+    if let Some(background) = style.background_color.as_deref().and_then(parse_color) {
+      let padding = style.background_padding.unwrap_or(0.3) * scale_factor;
+      let min = (left - padding, baseline - v_metrics.ascent - padding);
+      let max = (left + text_width + padding, baseline - v_metrics.descent + padding);
+      if let Some(mut mask) = CoverageMask::new(imgbuf, min, max) {
+        mask.fill_with(|_, _| 1.0);
+        mask.composite(imgbuf, background, opacity);
+      }
+    }
+
+    let stroke_width = style.stroke.as_ref().map_or(0.0, |_| style.stroke_width.unwrap_or(0.15) * scale_factor);
+    let margin = stroke_width + 2.0;
+    let min = (left - margin, baseline - v_metrics.ascent - margin);
+    let max = (left + text_width + margin, baseline - v_metrics.descent + margin);
+    let Some(mut glyphs) = CoverageMask::new(imgbuf, min, max) else { return };
+
+    for glyph in font.layout(text, scale, rusttype::point(left, baseline)) {
+      if let Some(bb) = glyph.pixel_bounding_box() {
+        glyph.draw(|gx, gy, coverage| glyphs.add(bb.min.x + gx as i32, bb.min.y + gy as i32, coverage));
+      }
+    }
+
+    if let Some(stroke) = style.stroke.as_deref().and_then(parse_color) {
+      // Approximate an outline by spreading the glyph coverage outwards
+      let radius = (stroke_width / 2.0).max(0.5);
+      let offsets: Vec<(i32, i32)> = (0..16)
+        .map(|i| {
+          let angle = i as f32 * std::f32::consts::TAU / 16.0;
+          ((angle.cos() * radius).round() as i32, (angle.sin() * radius).round() as i32)
+        })
+        .collect();
+      if let Some(mut outline) = CoverageMask::new(imgbuf, min, max) {
+        outline.fill_with(|px, py| {
+          let (x, y) = (px as i32, py as i32);
+          offsets.iter().map(|(dx, dy)| glyphs.get(x + dx, y + dy)).fold(0.0, f32::max)
+        });
+        outline.composite(imgbuf, stroke, opacity);
+      }
+    }
+
+    if let Some(color) = parse_color(style.color.as_deref().unwrap_or("#ffffff")) {
+      glyphs.composite(imgbuf, color, opacity);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_hex_colors() {
+    assert_eq!(parse_color("#fff"), Some(image::Rgba([255, 255, 255, 255])));
+    assert_eq!(parse_color("#1a2"), Some(image::Rgba([0x11, 0xaa, 0x22, 255])));
+    assert_eq!(parse_color("#12ab34"), Some(image::Rgba([0x12, 0xab, 0x34, 255])));
+    assert_eq!(parse_color("#12AB34"), Some(image::Rgba([0x12, 0xab, 0x34, 255])));
+    assert_eq!(parse_color("#12ab3480"), Some(image::Rgba([0x12, 0xab, 0x34, 0x80])));
+  }
+
+  #[test]
+  fn parses_rgb_functions() {
+    assert_eq!(parse_color("rgb(10, 20, 30)"), Some(image::Rgba([10, 20, 30, 255])));
+    assert_eq!(parse_color("rgba(10,20,30,0.5)"), Some(image::Rgba([10, 20, 30, 128])));
+    assert_eq!(parse_color("rgb(300, -5, 30)"), Some(image::Rgba([255, 0, 30, 255])));
+  }
+
+  #[test]
+  fn parses_named_colors() {
+    assert_eq!(parse_color("red"), Some(image::Rgba([255, 0, 0, 255])));
+    assert_eq!(parse_color(" White "), Some(image::Rgba([255, 255, 255, 255])));
+    assert_eq!(parse_color("grey"), parse_color("gray"));
+    assert_eq!(parse_color("transparent"), Some(image::Rgba([0, 0, 0, 0])));
+  }
+
+  #[test]
+  fn rejects_invalid_colors() {
+    for color in ["", "#", "#ff", "#ffff", "#fffff", "#fffffffff", "#ggg", "#12345g", "rgb(1, 2)", "rgb(a, b, c)", "rgba(1, 2, 3, x)", "rgb(1, 2, 3", "chartreuse"] {
+      assert_eq!(parse_color(color), None, "{:?}", color);
+    }
+  }
+
+  #[test]
+  fn formats_colors_that_parse_back() {
+    let color = image::Rgba([0x12, 0xab, 0x34, 0x80]);
+    assert_eq!(rgba_to_hex(color), "#12ab3480");
+    assert_eq!(parse_color(&rgba_to_hex(color)), Some(color));
+  }
+
+  #[test]
+  fn converts_font_sizes_to_cells() {
+    assert_eq!(FontStyle::Size(0.7).size(50.0), 0.7);
+    assert_eq!(FontStyle::Custom("0.7 serif".to_string()).size(50.0), 0.7);
+    assert_eq!(FontStyle::Custom("bold 1.5 Times New Roman".to_string()).size(50.0), 1.5);
+    assert_eq!(FontStyle::Custom("20px".to_string()).size(50.0), 0.4);
+    assert_eq!(FontStyle::Custom("italic 20px serif".to_string()).size(10.0), 2.0);
+    assert_eq!(FontStyle::Custom("serif".to_string()).size(50.0), DEFAULT_FONT_SIZE);
+  }
+}
@@ -0,0 +1,270 @@
+use screeps::local::{Position, RoomName};
+
+use super::*;
+use super::visual::rgba_to_hex;
+
+/// The height of room name labels, in room cells
+const ROOM_LABEL_FONT_SIZE: f32 = 4.0;
+
+/// An image of a rectangular region of the world map, drawn on using world positions
+///
+/// Rooms are laid out as on the in-game world map, with west on the left and
+/// north at the top, and share grid lines at their edges. The drawing
+/// primitives mirror the game's `MapVisual` API and take [Position]s, so
+/// shapes can span several rooms; anything outside the region is clipped.
+#[derive(Debug, Clone)]
+pub struct WorldCanvas {
+  ctx: RenderContext,
+  world_ctx: RenderContext,
+  top_left: RoomName,
+  rooms_wide: u32,
+  rooms_high: u32,
+  imgbuf: OutputImage,
+}
+
+impl WorldCanvas {
+  /// Creates a blank canvas covering every room between two corner rooms, inclusive
+  ///
+  /// The corners may be given in any order. Each room is drawn with the given context.
+  pub fn new(ctx: RenderContext, corner: RoomName, opposite_corner: RoomName) -> Self {
+    let (min_x, max_x) = (corner.x_coord().min(opposite_corner.x_coord()), corner.x_coord().max(opposite_corner.x_coord()));
+    let (min_y, max_y) = (corner.y_coord().min(opposite_corner.y_coord()), corner.y_coord().max(opposite_corner.y_coord()));
+    let top_left = corner.checked_add((min_x - corner.x_coord(), min_y - corner.y_coord())).unwrap_or(corner);
+    let rooms_wide = (max_x - min_x + 1) as u32;
+    let rooms_high = (max_y - min_y + 1) as u32;
+
+    let world_ctx = RenderContext {
+      room_max_cols: rooms_wide * ctx.room_max_cols,
+      room_max_rows: rooms_high * ctx.room_max_rows,
+      ..ctx
+    };
+
+    Self {
+      imgbuf: world_ctx.create_image(),
+      ctx,
+      world_ctx,
+      top_left,
+      rooms_wide,
+      rooms_high,
+    }
+  }
+
+  /// The context used to draw each room on this canvas
+  pub fn context(&self) -> &RenderContext {
+    &self.ctx
+  }
+
+  /// A context spanning the whole region, treating it as one large room
+  pub fn world_context(&self) -> &RenderContext {
+    &self.world_ctx
+  }
+
+  /// The room in the north-west corner of the region
+  pub fn top_left(&self) -> RoomName {
+    self.top_left
+  }
+
+  /// The number of rooms across and down the region
+  pub fn size_in_rooms(&self) -> (u32, u32) {
+    (self.rooms_wide, self.rooms_high)
+  }
+
+  /// Returns the rooms in the region, row by row from the north-west corner
+  pub fn rooms(&self) -> Vec<RoomName> {
+    (0..self.rooms_high as i32)
+      .flat_map(|dy| (0..self.rooms_wide as i32).map(move |dx| (dx, dy)))
+      .filter_map(|offset| self.top_left.checked_add(offset))
+      .collect()
+  }
+
+  /// Borrows the underlying image
+  pub fn image(&self) -> &OutputImage {
+    &self.imgbuf
+  }
+
+  /// Mutably borrows the underlying image, for drawing that this type does not cover
+  pub fn image_mut(&mut self) -> &mut OutputImage {
+    &mut self.imgbuf
+  }
+
+  /// Consumes the canvas, returning the underlying image
+  pub fn into_image(self) -> OutputImage {
+    self.imgbuf
+  }
+
+  /// Returns the column and row of a room relative to the north-west corner of the region, which may be outside of it
+  fn signed_room_offset(&self, room_name: RoomName) -> (i32, i32) {
+    (room_name.x_coord() - self.top_left.x_coord(), room_name.y_coord() - self.top_left.y_coord())
+  }
+
+  /// Returns the column and row of a room within the region, if it is in the region
+  pub fn room_offset(&self, room_name: RoomName) -> Option<(u32, u32)> {
+    let (dx, dy) = self.signed_room_offset(room_name);
+    if (dx < 0) | (dy < 0) | (dx >= self.rooms_wide as i32) | (dy >= self.rooms_high as i32) {
+      return None;
+    }
+    Some((dx as u32, dy as u32))
+  }
+
+  /// Returns the pixel location of the top-left corner of a room's image, including its border
+  pub fn room_origin(&self, room_name: RoomName) -> Result<(u32, u32), CanvasError> {
+    let (room_col, room_row) = self.room_offset(room_name).ok_or(CanvasError::OutsideRegion { room_name })?;
+    let (x, y) = self.world_ctx.cell_origin(room_col * self.ctx.room_max_cols, room_row * self.ctx.room_max_rows);
+    Ok((x - self.ctx.border_width, y - self.ctx.border_width))
+  }
+
+  /// Converts a world position to fractional cell coordinates on the whole canvas
+  ///
+  /// Rooms are laid out [RenderContext::room_max_cols] by [RenderContext::room_max_rows]
+  /// cells apart, as [WorldCanvas::room_origin] places them. Positions in rooms outside
+  /// of the region get coordinates off the canvas.
+  pub fn canvas_coords(&self, position: Position) -> (f32, f32) {
+    let (room_col, room_row) = self.signed_room_offset(position.room_name());
+    let x = room_col * self.ctx.room_max_cols as i32 + position.x().u8() as i32;
+    let y = room_row * self.ctx.room_max_rows as i32 + position.y().u8() as i32;
+    (x as f32, y as f32)
+  }
+
+  /// Copies an image of a single room, drawn with this canvas's context, into place
+  pub fn draw_room(&mut self, room_name: RoomName, room_img: &OutputImage) -> Result<(), CanvasError> {
+    let (x, y) = self.room_origin(room_name)?;
+    image::imageops::replace(&mut self.imgbuf, room_img, x.into(), y.into());
+    Ok(())
+  }
+
+  /// Draws a [RoomCanvas] into place, using the room name it was restricted to
+  pub fn draw_room_canvas(&mut self, canvas: &RoomCanvas) -> Result<(), CanvasError> {
+    let room_name = canvas.room_name().ok_or(CanvasError::MissingRoomName)?;
+    self.draw_room(room_name, canvas.image())
+  }
+
+  /// Draws the cell grid over the whole canvas
+  pub fn draw_grid(&mut self) {
+    self.world_ctx.draw_grid(&mut self.imgbuf);
+  }
+
+  /// Draws lines along the edges of every room in the theme's room border color
+  pub fn draw_room_borders(&mut self) {
+    use image::Pixel;
+
+    let room_width = self.ctx.room_max_cols * self.ctx.scale_factor;
+    let room_height = self.ctx.room_max_rows * self.ctx.scale_factor;
+    // A few pixels at most, so borders stand out from the grid without covering cells
+    let line_width = (self.ctx.scale_factor / 16).max(self.ctx.border_width).max(1) as i64;
+    let border_width = self.ctx.border_width as i64;
+    let color = self.ctx.theme.room_border;
+
+    // Compares pixel centers to the center of the nearest room edge's grid line, in half-pixels
+    let on_edge = |position: u32, room_size: u32| {
+      let edge = ((position + room_size / 2) / room_size * room_size) as i64;
+      (2 * position as i64 + 1 - (2 * edge + border_width)).abs() < line_width
+    };
+
+    for (x, y, pixel) in self.imgbuf.enumerate_pixels_mut() {
+      if on_edge(x, room_width) | on_edge(y, room_height) {
+        pixel.blend(&color);
+      }
+    }
+  }
+
+  /// Labels every room with its name in its top-left corner
+  pub fn draw_room_labels(&mut self) {
+    let style = TextStyle::default()
+      .font(ROOM_LABEL_FONT_SIZE)
+      .color(&rgba_to_hex(self.ctx.theme.text))
+      .align(TextAlign::Left)
+      .background_color(&rgba_to_hex(self.ctx.theme.background))
+      .background_padding(0.5)
+      .opacity(0.8);
+
+    for room_name in self.rooms() {
+      let Some((room_col, room_row)) = self.room_offset(room_name) else { continue };
+      let x = (room_col * self.ctx.room_max_cols) as f32 + 1.0;
+      let y = (room_row * self.ctx.room_max_rows) as f32 + ROOM_LABEL_FONT_SIZE;
+      self.world_ctx.draw_text(&mut self.imgbuf, &room_name.to_string(), x, y, &style);
+    }
+  }
+
+  /// Draws a circle centered on a position, like `MapVisual.circle`
+  pub fn draw_circle(&mut self, position: Position, style: &CircleStyle) {
+    let (x, y) = self.canvas_coords(position);
+    self.world_ctx.draw_circle(&mut self.imgbuf, x, y, style);
+  }
+
+  /// Draws a line between two positions, like `MapVisual.line`
+  pub fn draw_line(&mut self, from: Position, to: Position, style: &LineStyle) {
+    let (from, to) = (self.canvas_coords(from), self.canvas_coords(to));
+    self.world_ctx.draw_line(&mut self.imgbuf, from, to, style);
+  }
+
+  /// Draws a rectangle with its top-left corner at a position, like `MapVisual.rect`
+  pub fn draw_rect(&mut self, top_left: Position, width: f32, height: f32, style: &RectStyle) {
+    let (x, y) = self.canvas_coords(top_left);
+    self.world_ctx.draw_rect(&mut self.imgbuf, x, y, width, height, style);
+  }
+
+  /// Draws a polygon through a list of positions, like `MapVisual.poly`
+  pub fn draw_poly(&mut self, points: &[Position], style: &PolyStyle) {
+    let points: Vec<(f32, f32)> = points.iter().map(|position| self.canvas_coords(*position)).collect();
+    self.world_ctx.draw_poly(&mut self.imgbuf, &points, style);
+  }
+
+  /// Draws text with its baseline at a position, like `MapVisual.text`
+  pub fn draw_text(&mut self, position: Position, text: &str, style: &TextStyle) {
+    let (x, y) = self.canvas_coords(position);
+    self.world_ctx.draw_text(&mut self.imgbuf, text, x, y, style);
+  }
+
+  /// Draws a path through a list of positions, which may cross between rooms
+  pub fn draw_path(&mut self, path: &[Position], style: &PathStyle) {
+    let points: Vec<(f32, f32)> = path.iter().map(|position| self.canvas_coords(*position)).collect();
+    self.world_ctx.draw_path_points(&mut self.imgbuf, &points, style);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use screeps::local::RoomXY;
+
+  use super::*;
+
+  fn room(name: &str) -> RoomName {
+    name.parse().unwrap()
+  }
+
+  fn position(x: u8, y: u8, room_name: &str) -> Position {
+    let xy = RoomXY::checked_new(x, y).unwrap();
+    Position::new(xy.x, xy.y, room(room_name))
+  }
+
+  fn canvas(ctx: RenderContext) -> WorldCanvas {
+    WorldCanvas::new(ctx, room("E0S0"), room("W1N1"))
+  }
+
+  #[test]
+  fn lays_out_rooms_west_to_east_and_north_to_south() {
+    let canvas = canvas(RenderContext::with_scale_factor(2));
+    assert_eq!(canvas.top_left(), room("W1N1"));
+    assert_eq!(canvas.size_in_rooms(), (3, 3));
+    let names: Vec<String> = canvas.rooms().iter().map(RoomName::to_string).collect();
+    assert_eq!(names, ["W1N1", "W0N1", "E0N1", "W1N0", "W0N0", "E0N0", "W1S0", "W0S0", "E0S0"]);
+  }
+
+  #[test]
+  fn places_rooms_and_positions() {
+    let canvas = canvas(RenderContext::with_scale_factor(2));
+    assert_eq!(canvas.room_origin(room("E0N0")), Ok((200, 100)));
+    assert_eq!(canvas.room_origin(room("E1N0")), Err(CanvasError::OutsideRegion { room_name: room("E1N0") }));
+    assert_eq!(canvas.canvas_coords(position(10, 20, "E0N0")), (110.0, 70.0));
+    assert_eq!(canvas.canvas_coords(position(0, 0, "W1N1")), (0.0, 0.0));
+    assert_eq!(canvas.canvas_coords(position(49, 10, "W2N1")), (-1.0, 10.0));
+  }
+
+  #[test]
+  fn places_positions_in_smaller_rooms() {
+    let ctx = RenderContext { room_max_cols: 25, room_max_rows: 20, ..RenderContext::with_scale_factor(2) };
+    let canvas = canvas(ctx);
+    assert_eq!(canvas.room_origin(room("E0N0")), Ok((100, 40)));
+    assert_eq!(canvas.canvas_coords(position(10, 15, "E0N0")), (60.0, 35.0));
+  }
+}